@@ -0,0 +1,13 @@
+# Builds the WASM with the `wasm-threads` feature, so strategies can climb starting points on a pool of Web Workers.
+# Needs a nightly toolchain with the rust-src component, and the site must be served with the
+# `Cross-Origin-Opener-Policy: same-origin` and `Cross-Origin-Embedder-Policy: require-corp` headers so the page can
+# use shared memory. Without the pool started (or with the default `build.sh`), scheduling runs single-threaded.
+if ! command -v wasm-pack &> /dev/null
+then
+    echo "wasm-pack could not be found, installing"
+    cargo install wasm-pack
+fi
+
+RUSTFLAGS='-C target-feature=+atomics,+bulk-memory,+mutable-globals' \
+    rustup run nightly \
+    wasm-pack build --profiling --target web -- --features wasm-threads -Z build-std=panic_abort,std
@@ -0,0 +1,29 @@
+// Web Workers for the `wasm-threads` thread pool (see src/thread_pool.rs). wasm-bindgen copies this file to
+// pkg/snippets/<crate>-<hash>/js/, so the generated module is three directories up.
+const PKG = '../../../groups_core.js';
+const WORKER_NAME = 'groups_core_thread_pool';
+
+// Runs in each worker: load the WASM into the shared memory, tell the page we're ready, then become a rayon thread.
+if (self.name === WORKER_NAME) {
+    self.onmessage = async ({ data: { module, memory } }) => {
+        const pkg = await import(PKG);
+        await pkg.default({ module_or_path: module, memory });
+        postMessage('ready');
+        pkg.start_thread_pool_worker_wasm();
+    };
+}
+
+// Runs on the page: start the workers, wait until each has loaded the WASM, then build the pool.
+export async function startWorkers(module, memory, numThreads) {
+    await Promise.all(Array.from({ length: numThreads }, () => {
+        const worker = new Worker(new URL('./worker_helpers.js', import.meta.url), {
+            type: 'module',
+            name: WORKER_NAME,
+        });
+        worker.postMessage({ module, memory });
+        return new Promise(resolve => worker.addEventListener('message', resolve, { once: true }));
+    }));
+
+    const pkg = await import(PKG);
+    pkg.build_thread_pool_wasm(numThreads);
+}
@@ -4,6 +4,16 @@ pub mod constants;
 pub mod random;
 pub mod scheduling;
 pub mod student;
+// Also built for tests, which check the handoff of rayon threads to workers with native threads.
+#[cfg(any(
+    test,
+    all(
+        target_arch = "wasm32",
+        target_os = "unknown",
+        feature = "wasm-threads"
+    )
+))]
+mod thread_pool;
 pub mod timezones;
 
 /// Initializes the library for use in WASM. This function should be called before any others in this library in a
@@ -25,6 +25,15 @@ fn random_week_availability<R: Rng>(rng: &mut R) -> String {
     week_availability
 }
 
+/// A student in `timezone` available at exactly `hours` of their week, for tests that need particular availability.
+#[cfg(test)]
+pub(crate) fn student_with_hours(name: &str, timezone: &str, hours: &[usize]) -> Student {
+    let week: String = (0..NUM_HOURS_PER_WEEK)
+        .map(|h| if hours.contains(&h) { '1' } else { '0' })
+        .collect();
+    Student::new(name, timezone, &week).unwrap()
+}
+
 /// Generate `count` random student, optionally seeding the RNG with `seed`.
 /// Returns vec of students and the seed used.
 pub fn random_students(count: usize, seed: Option<u64>) -> (Vec<Student>, u64) {
@@ -0,0 +1,254 @@
+/// Several strong groupings of a class to choose between, rather than only the best one a strategy found, along with
+/// how alike they are. Groupings are compared by the pairs of students they put together.
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use super::hillclimbing_strategy::Problem;
+use super::{
+    DefaultStrategy, DisplayGroup, Group, GroupSizing, Layout, SchedulingOptions,
+    SchedulingStrategy, display_groups, group_indices,
+};
+use crate::student::Student;
+
+/// One of the groupings returned by `create_alternative_groups`.
+#[derive(Debug, PartialEq)]
+pub struct Alternative {
+    pub groups: Vec<Group>,
+    /// Score of `groups`, scored the way `HillClimbingStrategy` scores them whichever strategy made them.
+    pub score: f64,
+}
+
+/// Groupings returned by `create_alternative_groups`.
+#[derive(Debug, PartialEq)]
+pub struct Alternatives {
+    /// Best first.
+    pub alternatives: Vec<Alternative>,
+    /// `similarity[i][j]` is the `grouping_similarity` of alternatives `i` and `j`.
+    pub similarity: Vec<Vec<f64>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DisplayAlternative {
+    groups: Vec<DisplayGroup>,
+    score: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DisplayAlternatives {
+    alternatives: Vec<DisplayAlternative>,
+    similarity: Vec<Vec<f64>>,
+}
+
+#[wasm_bindgen]
+/// Same as `create_alternative_groups`, but suitable for calling from WASM because it takes and returns JSValues.
+/// `students` is a Javascript array of encoded Student (strings).
+/// `sizing` is a `GroupSizing` object, as in `create_groups_wasm`.
+/// `count` is the most groupings to return and `max_similarity` how alike any two may be, from 0 to 1.
+/// `output_timezone` is the timezone which will be used when generating the `suggested_meet_times` array in
+/// each output group.
+/// Returns a JSON object with `alternatives`, an array of `{ groups, score }` (`groups` as from `create_groups_wasm`)
+/// best first, and `similarity`, a matrix of how alike each pair of alternatives is.
+pub fn create_alternative_groups_wasm(
+    students: JsValue,
+    sizing: JsValue,
+    count: usize,
+    max_similarity: f64,
+    output_timezone: String,
+) -> JsValue {
+    let student_strings: Vec<String> = serde_wasm_bindgen::from_value(students).unwrap();
+    let sizing: GroupSizing = serde_wasm_bindgen::from_value(sizing).unwrap();
+
+    let result = create_alternative_groups::<DefaultStrategy>(
+        &student_strings,
+        sizing,
+        count,
+        max_similarity,
+    );
+    let display = DisplayAlternatives {
+        alternatives: result
+            .alternatives
+            .iter()
+            .map(|a| DisplayAlternative {
+                groups: display_groups(&a.groups, &output_timezone),
+                score: a.score,
+            })
+            .collect(),
+        similarity: result.similarity,
+    };
+    serde_wasm_bindgen::to_value(&display).unwrap()
+}
+
+/// Like `create_groups`, but returns up to `count` of the best groupings found, so an instructor can pick between them.
+/// Each is at most `max_similarity` similar (see `grouping_similarity`) to every better one, and no two are the same.
+/// Returns no groupings if `sizing` can't be met.
+pub fn create_alternative_groups<S: SchedulingStrategy>(
+    students_encoded: &[String],
+    sizing: impl Into<GroupSizing>,
+    count: usize,
+    max_similarity: f64,
+) -> Alternatives {
+    let (codes, students): (Vec<&String>, Vec<Student>) = students_encoded
+        .iter()
+        .filter_map(|s| Some((s, Student::from_encoded(s)?)))
+        .unzip();
+    let Some(layout) = sizing
+        .into()
+        .group_sizes(students.len())
+        .and_then(|sizes| Layout::new(sizes, &[]))
+    else {
+        return Alternatives {
+            alternatives: vec![],
+            similarity: vec![],
+        };
+    };
+
+    let groupings = S::run_alternatives(
+        &students,
+        &layout,
+        &SchedulingOptions::default(),
+        count,
+        max_similarity,
+    );
+
+    let similarity = groupings
+        .iter()
+        .map(|a| {
+            groupings
+                .iter()
+                .map(|b| grouping_similarity(a, b))
+                .collect()
+        })
+        .collect();
+    let problem = Problem::new(students, layout, SchedulingOptions::default());
+    let alternatives = groupings
+        .into_iter()
+        .map(|groups| {
+            let score: isize = group_indices(&codes, &groups)
+                .iter()
+                .map(|group| problem.score_group(group).0)
+                .sum();
+            Alternative {
+                groups,
+                score: score as f64,
+            }
+        })
+        .collect();
+
+    Alternatives {
+        alternatives,
+        similarity,
+    }
+}
+
+/// How alike two groupings of the same students are, from 0 (no two students who are together in one are together in
+/// the other) to 1 (the same groups, in any order). This is the share of pairs of students grouped together in either
+/// grouping that are grouped together in both.
+pub fn grouping_similarity(a: &[Group], b: &[Group]) -> f64 {
+    let mut labels: HashMap<&str, (Option<usize>, Option<usize>)> = HashMap::new();
+    for (g, group) in a.iter().enumerate() {
+        for code in &group.students {
+            labels.entry(code).or_default().0 = Some(g);
+        }
+    }
+    for (g, group) in b.iter().enumerate() {
+        for code in &group.students {
+            labels.entry(code).or_default().1 = Some(g);
+        }
+    }
+
+    // Students missing from either grouping can't be compared.
+    let (labels_a, labels_b): (Vec<usize>, Vec<usize>) = labels
+        .into_values()
+        .filter_map(|(a, b)| Some((a?, b?)))
+        .unzip();
+    label_similarity(&labels_a, &labels_b)
+}
+
+/// `grouping_similarity` of two groupings given as the group of each student.
+pub(crate) fn label_similarity(a: &[usize], b: &[usize]) -> f64 {
+    let mut in_a: HashMap<usize, u64> = HashMap::new();
+    let mut in_b: HashMap<usize, u64> = HashMap::new();
+    let mut in_both: HashMap<(usize, usize), u64> = HashMap::new();
+    for (&group_a, &group_b) in a.iter().zip(b) {
+        *in_a.entry(group_a).or_default() += 1;
+        *in_b.entry(group_b).or_default() += 1;
+        *in_both.entry((group_a, group_b)).or_default() += 1;
+    }
+
+    let (pairs_a, pairs_b, pairs_both) = (pairs(&in_a), pairs(&in_b), pairs(&in_both));
+    let pairs_either = pairs_a + pairs_b - pairs_both;
+    if pairs_either == 0 {
+        // Nobody is grouped with anybody in either grouping.
+        1.0
+    } else {
+        pairs_both as f64 / pairs_either as f64
+    }
+}
+
+/// Number of pairs of students who share a group, given how many students are in each group.
+fn pairs<K>(group_counts: &HashMap<K, u64>) -> u64 {
+    group_counts.values().map(|n| n * (n - 1) / 2).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::random_students;
+    use crate::scheduling::hillclimbing_strategy::HillClimbingStrategy;
+
+    fn groups(groups: &[&[&str]]) -> Vec<Group> {
+        groups
+            .iter()
+            .map(|g| Group {
+                students: g.iter().map(|s| s.to_string()).collect(),
+                suggested_meet_times: vec![],
+            })
+            .collect()
+    }
+
+    #[test]
+    fn similarity_of_groupings() {
+        let a = groups(&[&["a", "b", "c"], &["d", "e", "f"]]);
+        let reordered = groups(&[&["f", "e", "d"], &["b", "a", "c"]]);
+        assert_eq!(1.0, grouping_similarity(&a, &reordered));
+
+        // Only a-b and e-f are still together. 6 pairs in each grouping, 2 in both, so 2 of 10.
+        let b = groups(&[&["a", "b", "d"], &["c", "e", "f"]]);
+        assert_eq!(0.2, grouping_similarity(&a, &b));
+        assert_eq!(grouping_similarity(&b, &a), grouping_similarity(&a, &b));
+
+        let apart = groups(&[&["a", "d"], &["b", "e"], &["c", "f"]]);
+        assert_eq!(0.0, grouping_similarity(&a, &apart));
+    }
+
+    #[test]
+    fn alternatives_are_distinct_and_best_first() {
+        let (students, _) = random_students(30, Some(40));
+        let codes: Vec<String> = students.iter().map(|s| s.encode()).collect();
+
+        let result = create_alternative_groups::<HillClimbingStrategy>(&codes, 5, 4, 0.8);
+        assert_eq!(4, result.alternatives.len());
+        for (i, alternative) in result.alternatives.iter().enumerate() {
+            assert_eq!(6, alternative.groups.len());
+            assert_eq!(1.0, result.similarity[i][i]);
+            for j in 0..i {
+                assert!(result.alternatives[j].score >= alternative.score);
+                assert!(result.similarity[i][j] <= 0.8);
+                assert_eq!(result.similarity[i][j], result.similarity[j][i]);
+            }
+        }
+
+        // Asking for one gives just the best.
+        let best = create_alternative_groups::<HillClimbingStrategy>(&codes, 5, 1, 1.0);
+        assert_eq!(1, best.alternatives.len());
+    }
+
+    #[test]
+    fn no_alternatives_without_groups() {
+        let result = create_alternative_groups::<HillClimbingStrategy>(&[], 5, 3, 0.5);
+        assert!(result.alternatives.is_empty());
+        assert!(result.similarity.is_empty());
+    }
+}
@@ -0,0 +1,239 @@
+/// Week-long availability as plain 64 bit words, so strategies can combine students' availabilities with a few word
+/// operations instead of hour by hour. Students are converted to UTC once, when a strategy starts.
+use std::ops::{BitAnd, BitOr, BitXor};
+
+use crate::constants::NUM_HOURS_PER_WEEK;
+use crate::student::Student;
+
+const NUM_WORDS: usize = NUM_HOURS_PER_WEEK.div_ceil(64);
+
+/// One bit per hour of the week in UTC. 0 = Monday at 12 AM, 1 = Monday at 1 AM, etc. Bits past the end of the week
+/// are always clear.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct WeekBits([u64; NUM_WORDS]);
+
+impl WeekBits {
+    /// Every hour of the week.
+    pub(crate) fn all() -> Self {
+        let mut words = [u64::MAX; NUM_WORDS];
+        let used_in_last_word = NUM_HOURS_PER_WEEK - 64 * (NUM_WORDS - 1);
+        words[NUM_WORDS - 1] = u64::MAX >> (64 - used_in_last_word);
+        WeekBits(words)
+    }
+
+    pub(crate) fn from_hours(hours: impl IntoIterator<Item = usize>) -> Self {
+        let mut bits = WeekBits::default();
+        for hour in hours.into_iter().filter(|&hour| hour < NUM_HOURS_PER_WEEK) {
+            bits.0[hour / 64] |= 1 << (hour % 64);
+        }
+        bits
+    }
+
+    /// Hours `student` is available, in UTC.
+    pub(crate) fn from_student(student: &Student) -> Self {
+        let availability = student.availability_array_in_utc();
+        WeekBits::from_hours(availability[..NUM_HOURS_PER_WEEK].iter_ones())
+    }
+
+    pub(crate) fn count_ones(&self) -> u32 {
+        self.0.iter().map(|w| w.count_ones()).sum()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.iter().all(|&w| w == 0)
+    }
+
+    pub(crate) fn contains(&self, hour: usize) -> bool {
+        hour < NUM_HOURS_PER_WEEK && self.0[hour / 64] & (1 << (hour % 64)) != 0
+    }
+
+    /// Hours set in this but not in `other`.
+    pub(crate) fn without(&self, other: WeekBits) -> Self {
+        *self ^ (*self & other)
+    }
+
+    pub(crate) fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..NUM_HOURS_PER_WEEK).filter(|&hour| self.0[hour / 64] & (1 << (hour % 64)) != 0)
+    }
+
+    /// Moves every hour `by` hours earlier, dropping the first `by` hours of the week.
+    fn shifted_earlier(&self, by: usize) -> Self {
+        let (word_shift, bit_shift) = (by / 64, by % 64);
+        let mut words = [0; NUM_WORDS];
+        for (i, word) in words.iter_mut().enumerate() {
+            let Some(&low) = self.0.get(i + word_shift) else {
+                break;
+            };
+            *word = low >> bit_shift;
+            if bit_shift > 0
+                && let Some(&high) = self.0.get(i + word_shift + 1)
+            {
+                *word |= high << (64 - bit_shift);
+            }
+        }
+        WeekBits(words)
+    }
+
+    /// Moves every hour `by` hours later, dropping the last `by` hours of the week.
+    fn shifted_later(&self, by: usize) -> Self {
+        let (word_shift, bit_shift) = (by / 64, by % 64);
+        let mut words = [0; NUM_WORDS];
+        for (i, word) in words.iter_mut().enumerate().skip(word_shift) {
+            *word = self.0[i - word_shift] << bit_shift;
+            if bit_shift > 0 && i > word_shift {
+                *word |= self.0[i - word_shift - 1] >> (64 - bit_shift);
+            }
+        }
+        WeekBits(words) & WeekBits::all()
+    }
+
+    /// Hours that start a run of at least `length` consecutive set hours. Runs don't wrap around the end of the week.
+    pub(crate) fn run_starts(&self, length: usize) -> Self {
+        (1..length).fold(*self, |runs, by| runs & self.shifted_earlier(by))
+    }
+
+    /// Hours that are part of a run of at least `length` consecutive set hours.
+    pub(crate) fn in_runs(&self, length: usize) -> Self {
+        let starts = self.run_starts(length);
+        (1..length).fold(starts, |hours, by| hours | starts.shifted_later(by))
+    }
+
+    /// Length of the longest run of consecutive set hours. Runs don't wrap around the end of the week.
+    pub(crate) fn longest_run(&self) -> usize {
+        let mut length = 0;
+        let mut runs = *self;
+        while !runs.is_empty() {
+            length += 1;
+            runs = runs & runs.shifted_earlier(1);
+        }
+        length
+    }
+}
+
+impl BitAnd for WeekBits {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        WeekBits(std::array::from_fn(|i| self.0[i] & rhs.0[i]))
+    }
+}
+
+impl BitOr for WeekBits {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        WeekBits(std::array::from_fn(|i| self.0[i] | rhs.0[i]))
+    }
+}
+
+impl BitXor for WeekBits {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self {
+        WeekBits(std::array::from_fn(|i| self.0[i] ^ rhs.0[i]))
+    }
+}
+
+/// Enough bit planes to count up to 65535 students at an hour.
+const MAX_COUNT_PLANES: usize = 16;
+
+/// How many of a group's students are available at each hour of the week, stored bit-sliced: plane `p` holds bit `p`
+/// of every hour's count, so adding a student is a ripple-carry add over a few words.
+#[derive(Debug, Default)]
+pub(crate) struct HourCounts {
+    planes: [WeekBits; MAX_COUNT_PLANES],
+    num_planes: usize,
+}
+
+impl HourCounts {
+    pub(crate) fn new<'a>(availabilities: impl IntoIterator<Item = &'a WeekBits>) -> Self {
+        let mut counts = HourCounts::default();
+        for &availability in availabilities {
+            counts.add(availability);
+        }
+        counts
+    }
+
+    fn add(&mut self, availability: WeekBits) {
+        let mut carry = availability;
+        for plane in &mut self.planes[..self.num_planes] {
+            let next_carry = *plane & carry;
+            *plane = *plane ^ carry;
+            carry = next_carry;
+            if carry.is_empty() {
+                return;
+            }
+        }
+        if !carry.is_empty() {
+            self.planes[self.num_planes] = carry;
+            self.num_planes += 1;
+        }
+    }
+
+    /// The most students available at any one hour, and the hours when that many are.
+    pub(crate) fn max(&self) -> (u32, WeekBits) {
+        // Narrow down to the hours with the highest count, one bit of the count at a time from the top.
+        let mut hours = WeekBits::all();
+        let mut max = 0;
+        for (p, &plane) in self.planes[..self.num_planes].iter().enumerate().rev() {
+            let with_bit = hours & plane;
+            if !with_bit.is_empty() {
+                hours = with_bit;
+                max |= 1 << p;
+            }
+        }
+        (max, hours)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_and_shifts() {
+        let bits = WeekBits::from_hours([1, 2, 3, 4, 62, 63, 64, 65, 100, 166, 167, 168, 500]);
+        assert_eq!(11, bits.iter_ones().count());
+        assert_eq!(11, bits.count_ones());
+        assert_eq!(
+            vec![1, 62],
+            bits.run_starts(4).iter_ones().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![1, 2, 3, 62, 63, 64, 166],
+            bits.run_starts(2).iter_ones().collect::<Vec<_>>()
+        );
+        assert!(bits.run_starts(5).is_empty());
+        assert_eq!(4, bits.longest_run());
+        assert_eq!(
+            vec![1, 2, 3, 4, 62, 63, 64, 65, 166, 167],
+            bits.in_runs(2).iter_ones().collect::<Vec<_>>()
+        );
+        assert_eq!(bits, bits.in_runs(1));
+        assert!(bits.contains(167) && !bits.contains(5) && !bits.contains(500));
+        assert_eq!(0, WeekBits::default().longest_run());
+        assert_eq!(NUM_HOURS_PER_WEEK, WeekBits::all().longest_run());
+        assert_eq!(NUM_HOURS_PER_WEEK, WeekBits::all().iter_ones().count());
+    }
+
+    #[test]
+    fn counts_match_hour_by_hour() {
+        let availabilities = [
+            WeekBits::from_hours([0, 1, 2, 10]),
+            WeekBits::from_hours([1, 2, 10, 20]),
+            WeekBits::from_hours([2, 10, 20]),
+            WeekBits::from_hours([2, 10, 167]),
+        ];
+        let counts = HourCounts::new(&availabilities);
+        let (max, hours) = counts.max();
+        assert_eq!(4, max);
+        assert_eq!(vec![2, 10], hours.iter_ones().collect::<Vec<_>>());
+
+        let counts = HourCounts::new(&availabilities[..2]);
+        assert_eq!((2, WeekBits::from_hours([1, 2, 10])), counts.max());
+
+        // Nobody is ever available, so every hour ties at zero.
+        let counts = HourCounts::new(&[WeekBits::default(); 3]);
+        assert_eq!((0, WeekBits::all()), counts.max());
+    }
+}
@@ -0,0 +1,185 @@
+/// This strategy is for very large classes, where climbing from many random starting points takes too long to find
+/// good groups. Students are first clustered by availability (`Seeding::Similarity`), so students with similar weeks
+/// start out in the same or neighboring groups. The groups are then refined by hill climbing, mostly swapping
+/// students between nearby groups.
+use crate::scheduling::Group;
+use crate::student::Student;
+use rand::{Rng, rng};
+
+use super::hillclimbing_strategy::{Assignment, Problem, TopAssignments, climb_in_batches};
+use super::search::SearchClock;
+use super::{Layout, SchedulingOptions, SchedulingStrategy, Seeding};
+
+// Seeding is randomized, so seed and refine a few times and keep the best.
+pub(super) const NUM_STARTING_POINTS: usize = 8;
+
+// Swap partners are mostly picked from within this many groups of each other, which hold students from the same or
+// the next cluster.
+const NEARBY_GROUPS: usize = 4;
+
+// One in this many swaps is with any student, so groups can still improve across clusters.
+const FAR_SWAP_ODDS: u32 = 10;
+
+// Give up refining after this many swaps per unlocked student in a row don't score better.
+const TRIES_PER_STUDENT: usize = 20;
+
+pub struct ClusteringStrategy;
+
+impl SchedulingStrategy for ClusteringStrategy {
+    fn run(students: &[Student], layout: &Layout, options: &SchedulingOptions) -> Vec<Group> {
+        Self::run_alternatives(students, layout, options, 1, 1.0)
+            .into_iter()
+            .next()
+            .unwrap_or_default()
+    }
+
+    fn run_alternatives(
+        students: &[Student],
+        layout: &Layout,
+        options: &SchedulingOptions,
+        count: usize,
+        max_similarity: f64,
+    ) -> Vec<Vec<Group>> {
+        if students.is_empty() {
+            return vec![];
+        }
+
+        let problem = Problem::new(Vec::from(students), layout.clone(), options.clone());
+        let clock = options.search.start();
+        let top = TopAssignments::new(count, max_similarity);
+        let assignments =
+            climb_in_batches(&problem, &clock, NUM_STARTING_POINTS, top, |assignment| {
+                let seeding = options.seeding.unwrap_or(Seeding::Similarity);
+                let arrangement =
+                    seeding.arrangement(&problem.layout, &problem.availability, &mut rng());
+                assignment.start_from(&problem, arrangement);
+                refine(assignment, &problem, &clock);
+            });
+
+        assignments.iter().map(|a| a.groups(&problem)).collect()
+    }
+}
+
+/// Hill climbs from the seeded arrangement, mostly swapping students between nearby groups.
+fn refine(assignment: &mut Assignment, problem: &Problem, clock: &SearchClock) {
+    let swaps = NearbySwaps::new(problem);
+    let mut rng = rng();
+    let mut tries_without_improvement = 0;
+    while !swaps.finished(problem, tries_without_improvement)
+        && !clock.should_stop()
+        && assignment.score < problem.upper_bound
+    {
+        let (a, b) = swaps.pick(problem, &mut rng);
+        if assignment.try_swap(problem, a, b) {
+            tries_without_improvement = 0;
+        } else {
+            tries_without_improvement += 1;
+        }
+    }
+}
+
+/// Picks the swaps refining tries, mostly between students in nearby groups.
+pub(super) struct NearbySwaps {
+    /// Number of unlocked positions on either side of a student its swap partner is usually picked from.
+    window: usize,
+}
+
+impl NearbySwaps {
+    pub(super) fn new(problem: &Problem) -> Self {
+        // Unlocked positions are in order, so nearby positions are in nearby groups.
+        let largest_group = problem.layout.group_sizes().iter().max().copied();
+        NearbySwaps {
+            window: NEARBY_GROUPS * largest_group.unwrap_or(0),
+        }
+    }
+
+    /// Whether to give up refining after `tries_without_improvement` swaps in a row didn't score better.
+    pub(super) fn finished(&self, problem: &Problem, tries_without_improvement: usize) -> bool {
+        let movable = problem.layout.movable();
+        movable.len() < 2 || tries_without_improvement >= TRIES_PER_STUDENT * movable.len()
+    }
+
+    /// Positions of two unlocked students to try swapping. There must be at least one.
+    pub(super) fn pick(&self, problem: &Problem, rng: &mut impl Rng) -> (usize, usize) {
+        let movable = problem.layout.movable();
+        let a = rng.random_range(0..movable.len());
+        let b = if rng.random_ratio(1, FAR_SWAP_ODDS) {
+            rng.random_range(0..movable.len())
+        } else {
+            rng.random_range(
+                a.saturating_sub(self.window)..(a + self.window + 1).min(movable.len()),
+            )
+        };
+        (movable[a], movable[b])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::random::random_students;
+    use crate::scheduling::hillclimbing_strategy::HillClimbingStrategy;
+
+    #[test]
+    fn large_class_keeps_everyone_and_locked_groups() {
+        let (students, _) = random_students(1000, Some(1000));
+        let layout = Layout::new(vec![5; 200], &[vec![7, 500, 999]]).unwrap();
+        let groups = ClusteringStrategy::run(&students, &layout, &SchedulingOptions::default());
+        assert_eq!(200, groups.len());
+
+        let mut codes = groups.iter().flat_map(|g| g.students.clone()).collect_vec();
+        codes.sort_unstable();
+        let mut expected = students.iter().map(|s| s.encode()).collect_vec();
+        expected.sort_unstable();
+        assert_eq!(expected, codes);
+
+        let locked = [7, 500, 999].map(|s| students[s].encode());
+        assert!(
+            groups
+                .iter()
+                .any(|g| locked.iter().all(|s| g.students.contains(s)))
+        );
+    }
+
+    // Run with `cargo test --release -- --ignored --nocapture bench_clustering`.
+    #[test]
+    #[ignore]
+    fn bench_clustering_1000_students() {
+        let (students, _) = random_students(1000, Some(1000));
+        let layout = Layout::new(vec![5; 200], &[]).unwrap();
+        let options = SchedulingOptions::default();
+        let summary = |groups: &[Group]| {
+            let everyone_meets = groups
+                .iter()
+                .filter(|g| g.percent_at_suggested_times() == 1.0)
+                .count();
+            let mean_percent = groups
+                .iter()
+                .map(|g| g.percent_at_suggested_times())
+                .sum::<f64>()
+                / groups.len() as f64;
+            format!(
+                "{everyone_meets} of {} groups can all meet, {:.1}% of each group on average",
+                groups.len(),
+                mean_percent * 100.0
+            )
+        };
+
+        let started = Instant::now();
+        let clustered = ClusteringStrategy::run(&students, &layout, &options);
+        let clustering_time = started.elapsed();
+        let started = Instant::now();
+        let climbed = HillClimbingStrategy::run(&students, &layout, &options);
+        let climbing_time = started.elapsed();
+
+        println!(
+            "1000 students, 200 groups. Clustering: {clustering_time:?}, {}. Hill climbing: {climbing_time:?}, {}.",
+            summary(&clustered),
+            summary(&climbed)
+        );
+    }
+}
@@ -0,0 +1,231 @@
+/// Scores any grouping of students, such as one an instructor made by hand, the same way generated groupings are
+/// scored, so it can be compared with what the strategies come up with.
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use super::hillclimbing_strategy::Problem;
+use super::pareto::{gini, score_per_student};
+use super::{DisplayGroup, Group, Layout, SchedulingOptions, display_groups, pretty_hours};
+use crate::student::Student;
+
+/// How good one group of a grouping evaluated by `evaluate_groups` is.
+#[derive(Debug, PartialEq)]
+pub struct GroupEvaluation {
+    /// The group's students, with the hours suggested for it to meet as for generated groups.
+    pub group: Group,
+    /// Score of the group, scored the way `HillClimbingStrategy` scores groups.
+    pub score: f64,
+    /// `score` divided by the number of students in the group, so groups of different sizes compare.
+    pub score_per_student: f64,
+    /// Hours in the week when every student in the group is available (in UTC). 0 = Monday at 12 AM, etc.
+    pub common_hours: Vec<usize>,
+    /// Most consecutive `common_hours`.
+    pub longest_common_block: usize,
+}
+
+/// How good a grouping evaluated by `evaluate_groups` is, under each objective the strategies use.
+#[derive(Debug, PartialEq)]
+pub struct Evaluation {
+    /// In the order they were given, leaving out groups without any valid students.
+    pub groups: Vec<GroupEvaluation>,
+    /// Sum of the groups' scores. This is what `HillClimbingStrategy` maximizes.
+    pub score: f64,
+    /// No grouping of these students into groups of these sizes scores higher than this.
+    pub upper_bound: f64,
+    /// Sum of the number of `common_hours` of each group.
+    pub total_shared_hours: usize,
+    /// Lowest `score_per_student` of any group.
+    pub worst_group_score: f64,
+    /// Gini coefficient of the `score_per_student` of each student's group, from 0 (every group as good) towards 1.
+    pub inequality: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DisplayGroupEvaluation {
+    #[serde(flatten)]
+    group: DisplayGroup,
+    score: f64,
+    score_per_student: f64,
+    common_hours: Vec<String>,
+    longest_common_block: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DisplayEvaluation {
+    groups: Vec<DisplayGroupEvaluation>,
+    score: f64,
+    upper_bound: f64,
+    total_shared_hours: usize,
+    worst_group_score: f64,
+    inequality: f64,
+}
+
+#[wasm_bindgen]
+/// Same as `evaluate_groups`, but suitable for calling from WASM because it takes and returns JSValues.
+/// `groups` is a Javascript array of groups, each an array of encoded Student (strings).
+/// `output_timezone` is the timezone which will be used when generating the `suggested_meet_times` and
+/// `common_hours` arrays in each output group.
+/// Returns a JSON object with the overall `score`, `upper_bound`, `total_shared_hours`, `worst_group_score` and
+/// `inequality`, and `groups`, an array of groups as from `create_groups_wasm` which also have their `score`,
+/// `score_per_student`, `common_hours` and `longest_common_block`.
+pub fn evaluate_groups_wasm(groups: JsValue, output_timezone: String) -> JsValue {
+    let groups: Vec<Vec<String>> = serde_wasm_bindgen::from_value(groups).unwrap();
+
+    let evaluation = evaluate_groups(&groups);
+    let display = DisplayEvaluation {
+        groups: evaluation
+            .groups
+            .iter()
+            .map(|g| DisplayGroupEvaluation {
+                group: display_groups(std::slice::from_ref(&g.group), &output_timezone)
+                    .pop()
+                    .unwrap(),
+                score: g.score,
+                score_per_student: g.score_per_student,
+                common_hours: pretty_hours(&g.common_hours, &output_timezone),
+                longest_common_block: g.longest_common_block,
+            })
+            .collect(),
+        score: evaluation.score,
+        upper_bound: evaluation.upper_bound,
+        total_shared_hours: evaluation.total_shared_hours,
+        worst_group_score: evaluation.worst_group_score,
+        inequality: evaluation.inequality,
+    };
+    serde_wasm_bindgen::to_value(&display).unwrap()
+}
+
+/// Evaluates `groups`, each a list of encoded students, as the strategies would score them. Codes that don't decode
+/// are left out.
+pub fn evaluate_groups(groups: &[Vec<String>]) -> Evaluation {
+    let groups: Vec<Vec<(&String, Student)>> = groups
+        .iter()
+        .map(|g| {
+            g.iter()
+                .filter_map(|code| Some((code, Student::from_encoded(code)?)))
+                .collect::<Vec<_>>()
+        })
+        .filter(|g| !g.is_empty())
+        .collect();
+    let sizes = groups.iter().map(|g| g.len()).collect();
+    let (codes, students): (Vec<&String>, Vec<Student>) = groups.into_iter().flatten().unzip();
+    // Groups without students were left out, so every group size is valid.
+    let layout = Layout::new(sizes, &[]).unwrap();
+
+    // Students are laid out in the order given, so the layout's groups are the given groups.
+    let problem = Problem::new(students, layout, SchedulingOptions::default());
+    let arrangement: Vec<usize> = (0..codes.len()).collect();
+    let groups: Vec<GroupEvaluation> = problem
+        .layout
+        .groups(&arrangement)
+        .map(|members| {
+            let (score, meet_hours) = problem.score_group(members);
+            let common_hours = problem.common_hours(members);
+            GroupEvaluation {
+                group: Group {
+                    students: members.iter().map(|&s| codes[s].clone()).collect(),
+                    suggested_meet_times: meet_hours.iter_ones().collect(),
+                },
+                score: score as f64,
+                score_per_student: score_per_student(score, members.len()),
+                common_hours: common_hours.iter_ones().collect(),
+                longest_common_block: common_hours.longest_run(),
+            }
+        })
+        .collect();
+
+    let sizes_and_scores: Vec<(f64, usize)> = groups
+        .iter()
+        .map(|g| (g.score_per_student, g.group.students.len()))
+        .collect();
+    Evaluation {
+        score: groups.iter().map(|g| g.score).sum(),
+        upper_bound: problem.upper_bound as f64,
+        total_shared_hours: groups.iter().map(|g| g.common_hours.len()).sum(),
+        worst_group_score: sizes_and_scores
+            .iter()
+            .map(|&(score, _)| score)
+            .reduce(f64::min)
+            .unwrap_or(0.0),
+        inequality: gini(&sizes_and_scores),
+        groups,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{random_students, student_with_hours};
+    use crate::scheduling::create_groups_with_bound;
+    use crate::scheduling::hillclimbing_strategy::HillClimbingStrategy;
+    use crate::scheduling::search::SearchControl;
+
+    #[test]
+    fn scores_generated_groups_as_generated() {
+        let (students, _) = random_students(20, Some(20));
+        let codes: Vec<String> = students.iter().map(|s| s.encode()).collect();
+        let generated =
+            create_groups_with_bound::<HillClimbingStrategy>(&codes, 4, SearchControl::default());
+
+        let grouping: Vec<Vec<String>> = generated
+            .groups
+            .iter()
+            .map(|g| g.students.clone())
+            .collect();
+        let evaluation = evaluate_groups(&grouping);
+        assert_eq!(generated.score, evaluation.score);
+        assert_eq!(generated.upper_bound, evaluation.upper_bound);
+        for (evaluated, group) in evaluation.groups.iter().zip(&generated.groups) {
+            assert_eq!(group, &evaluated.group);
+            // When everyone in a group shares some hours, those are the suggested ones.
+            if !evaluated.common_hours.is_empty() {
+                assert_eq!(group.suggested_meet_times, evaluated.common_hours);
+            }
+        }
+    }
+
+    #[test]
+    fn no_groups() {
+        let evaluation = evaluate_groups(&[]);
+        assert!(evaluation.groups.is_empty());
+        assert_eq!(
+            (0.0, 0.0, 0.0),
+            (
+                evaluation.score,
+                evaluation.worst_group_score,
+                evaluation.inequality
+            )
+        );
+    }
+
+    #[test]
+    fn hand_made_groups() {
+        // Both available Monday 12 to 4 AM, Wednesday 1 and 2 AM, and one of them Friday at 3 AM.
+        let a = student_with_hours("a", "UTC", &[0, 1, 2, 3, 49, 50]);
+        let b = student_with_hours("b", "UTC", &[0, 1, 2, 3, 49, 50, 99]);
+        let nobody = student_with_hours("nobody", "UTC", &[]);
+
+        let evaluation = evaluate_groups(&[
+            vec![a.encode(), b.encode()],
+            vec![nobody.encode(), "not a student".to_string()],
+            vec![],
+        ]);
+        assert_eq!(2, evaluation.groups.len());
+
+        let together = &evaluation.groups[0];
+        assert_eq!(vec![0, 1, 2, 3, 49, 50], together.common_hours);
+        assert_eq!(4, together.longest_common_block);
+        assert_eq!(8.0, together.score);
+        assert_eq!(4.0, together.score_per_student);
+
+        let alone = &evaluation.groups[1];
+        assert_eq!(vec![nobody.encode()], alone.group.students);
+        assert!(alone.common_hours.is_empty());
+        assert_eq!(0, alone.longest_common_block);
+
+        assert_eq!(8.0, evaluation.score);
+        assert_eq!(6, evaluation.total_shared_hours);
+        assert_eq!(0.0, evaluation.worst_group_score);
+        assert!(evaluation.inequality > 0.0);
+    }
+}
@@ -0,0 +1,134 @@
+/// Tracks which students have already worked together in earlier assignments (for example earlier projects in the
+/// same term), so strategies can steer away from grouping them again.
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Score taken off a group for each forbidden pair in it, as a fraction of a perfect group's score. Large enough
+/// that no amount of shared availability makes up for a forbidden pair.
+const FORBIDDEN_PAIR_PENALTY: f64 = 10.0;
+
+/// How strongly to avoid grouping students who have worked together before.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RepeatPartnerRules {
+    /// Score taken off a group for each pair in it who have worked together before, as a fraction of a perfect
+    /// group's score. Zero ignores history unless `max_times_together` is set.
+    pub penalty: f64,
+    /// Pairs who have already worked together this many times can't be grouped again. None allows any number of
+    /// repeats.
+    pub max_times_together: Option<u32>,
+}
+
+impl Default for RepeatPartnerRules {
+    fn default() -> Self {
+        RepeatPartnerRules {
+            penalty: 0.5,
+            max_times_together: None,
+        }
+    }
+}
+
+/// How many times each pair of students has been in the same group before. Students are indices into the students
+/// passed to a strategy.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PartnerHistory {
+    /// Keyed by (smaller index, larger index).
+    times_together: HashMap<(usize, usize), u32>,
+}
+
+impl PartnerHistory {
+    /// Builds the history from earlier groupings, each a list of groups of student indices.
+    pub fn new(previous_groupings: &[Vec<Vec<usize>>]) -> Self {
+        let mut times_together = HashMap::new();
+        for group in previous_groupings.iter().flatten() {
+            for (i, &a) in group.iter().enumerate() {
+                for &b in &group[(i + 1)..] {
+                    if a != b {
+                        *times_together.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+
+        PartnerHistory { times_together }
+    }
+
+    /// Number of earlier groups `a` and `b` were both in.
+    pub fn times_together(&self, a: usize, b: usize) -> u32 {
+        self.times_together
+            .get(&(a.min(b), a.max(b)))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Number of pairs in `group` who have worked together before.
+    pub fn repeat_pairs(&self, group: &[usize]) -> usize {
+        self.pairs(group).filter(|&times| times > 0).count()
+    }
+
+    /// Score taken off `group` under `rules`, as a fraction of a perfect group's score.
+    pub fn penalty(&self, group: &[usize], rules: &RepeatPartnerRules) -> f64 {
+        if self.times_together.is_empty() {
+            return 0.0;
+        }
+
+        self.pairs(group)
+            .filter(|&times| times > 0)
+            .map(|times| match rules.max_times_together {
+                Some(max) if times >= max => FORBIDDEN_PAIR_PENALTY,
+                _ => rules.penalty * times as f64,
+            })
+            .sum()
+    }
+
+    /// Times together for every pair in `group`.
+    fn pairs<'a>(&'a self, group: &'a [usize]) -> impl Iterator<Item = u32> + 'a {
+        group.iter().enumerate().flat_map(move |(i, &a)| {
+            group[(i + 1)..]
+                .iter()
+                .map(move |&b| self.times_together(a, b))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_pairs_across_groupings() {
+        let history = PartnerHistory::new(&[
+            vec![vec![0, 1, 2], vec![3, 4]],
+            vec![vec![1, 0], vec![2, 3, 4]],
+        ]);
+
+        assert_eq!(history.times_together(0, 1), 2);
+        assert_eq!(history.times_together(1, 0), 2);
+        assert_eq!(history.times_together(3, 4), 2);
+        assert_eq!(history.times_together(2, 3), 1);
+        assert_eq!(history.times_together(0, 3), 0);
+        assert_eq!(history.repeat_pairs(&[0, 1, 3]), 1);
+        assert_eq!(history.repeat_pairs(&[0, 3]), 0);
+    }
+
+    #[test]
+    fn penalty_weights_and_forbids() {
+        let history = PartnerHistory::new(&[vec![vec![0, 1]], vec![vec![0, 1, 2]]]);
+        let rules = RepeatPartnerRules {
+            penalty: 0.25,
+            max_times_together: None,
+        };
+
+        // 0 and 1 worked together twice, 1 and 2 once.
+        assert_eq!(history.penalty(&[0, 1], &rules), 0.5);
+        assert_eq!(history.penalty(&[1, 2, 3], &rules), 0.25);
+        assert_eq!(history.penalty(&[3, 4], &rules), 0.0);
+
+        let strict = RepeatPartnerRules {
+            max_times_together: Some(2),
+            ..rules
+        };
+        assert_eq!(history.penalty(&[0, 1], &strict), FORBIDDEN_PAIR_PENALTY);
+        assert_eq!(history.penalty(&[1, 2], &strict), 0.25);
+    }
+}
@@ -0,0 +1,210 @@
+/// Recommends when a group should meet. `Group::suggested_meet_times` lists every hour the most members are available,
+/// which is often dozens of scattered hours; this instead proposes blocks of a requested length, ranked by how many
+/// members can attend all of them, then by how many of those hours are at a reasonable time of day for each attendee,
+/// then by how many other days of the week the same block works just as well.
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use super::availability::WeekBits;
+use super::pretty_hours;
+use crate::constants::{NUM_HOURS_PER_DAY, NUM_HOURS_PER_WEEK};
+use crate::student::Student;
+
+/// Hours of the day, in a student's own timezone, considered a reasonable time to meet.
+const DAYTIME_HOURS: Range<usize> = 8..22;
+
+/// A block of consecutive hours for a group to meet, from `recommend_meeting_slots`.
+#[derive(Debug, PartialEq)]
+pub struct MeetingSlot {
+    /// Hour of the week the slot starts at (in UTC). 0 = Monday at 12 AM, 1 = Monday at 1 AM, etc.
+    pub start: usize,
+    /// Encoded Students available for the whole slot.
+    pub attendees: Vec<String>,
+    /// Encoded Students not available for all of the slot.
+    pub absentees: Vec<String>,
+    /// Fraction of the attendees' hours in the slot that are daytime where they live.
+    pub daytime: f64,
+    /// Number of other days of the week the same hours suit at least as many members.
+    pub repeats_on_other_days: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DisplayMeetingSlot {
+    start: String,
+    attendees: Vec<String>,
+    absentees: Vec<String>,
+    daytime: f64,
+    repeats_on_other_days: usize,
+}
+
+#[wasm_bindgen]
+/// Same as `recommend_meeting_slots`, but suitable for calling from WASM because it takes and returns JSValues.
+/// `group` is a Javascript array of encoded Student (strings).
+/// `output_timezone` is the timezone which will be used when displaying the start of each slot.
+/// Returns a Javascript array of `{ start, attendees, absentees, daytime, repeats_on_other_days }` objects, best first.
+pub fn recommend_meeting_slots_wasm(
+    group: JsValue,
+    duration: usize,
+    count: usize,
+    output_timezone: String,
+) -> JsValue {
+    let group: Vec<String> = serde_wasm_bindgen::from_value(group).unwrap();
+
+    let display: Vec<DisplayMeetingSlot> = recommend_meeting_slots(&group, duration, count)
+        .into_iter()
+        .map(|slot| DisplayMeetingSlot {
+            start: pretty_hours(&[slot.start], &output_timezone).remove(0),
+            attendees: slot.attendees,
+            absentees: slot.absentees,
+            daytime: slot.daytime,
+            repeats_on_other_days: slot.repeats_on_other_days,
+        })
+        .collect();
+    serde_wasm_bindgen::to_value(&display).unwrap()
+}
+
+/// Up to `count` non-overlapping blocks of `duration` consecutive hours for the students in `group` to meet, best
+/// first. Slots don't wrap around the end of the week, and slots nobody can attend are left out. Codes that don't
+/// decode are left out.
+pub fn recommend_meeting_slots(
+    group: &[String],
+    duration: usize,
+    count: usize,
+) -> Vec<MeetingSlot> {
+    if duration == 0 || duration > NUM_HOURS_PER_WEEK {
+        return vec![];
+    }
+
+    let members: Vec<(&String, Student)> = group
+        .iter()
+        .filter_map(|code| Some((code, Student::from_encoded(code)?)))
+        .collect();
+    // Hours each member can stay for the whole slot from.
+    let can_start: Vec<WeekBits> = members
+        .iter()
+        .map(|(_, s)| WeekBits::from_student(s).run_starts(duration))
+        .collect();
+
+    let starts = 0..=NUM_HOURS_PER_WEEK - duration;
+    let attendance: Vec<usize> = starts
+        .clone()
+        .map(|start| can_start.iter().filter(|c| c.contains(start)).count())
+        .collect();
+
+    let mut slots: Vec<MeetingSlot> = starts
+        .filter(|&start| attendance[start] > 0)
+        .map(|start| {
+            let (attending, absent): (Vec<_>, Vec<_>) = members
+                .iter()
+                .zip(&can_start)
+                .partition(|(_, c)| c.contains(start));
+
+            let daytime_hours: usize = attending
+                .iter()
+                .map(|((_, student), _)| {
+                    let offset = student.utc_offset_hours() as isize;
+                    (start..start + duration)
+                        .filter(|&hour| {
+                            let local =
+                                (hour as isize + offset).rem_euclid(NUM_HOURS_PER_DAY as isize);
+                            DAYTIME_HOURS.contains(&(local as usize))
+                        })
+                        .count()
+                })
+                .sum();
+            let repeats_on_other_days = (start % NUM_HOURS_PER_DAY..attendance.len())
+                .step_by(NUM_HOURS_PER_DAY)
+                .filter(|&other| other != start && attendance[other] >= attendance[start])
+                .count();
+
+            MeetingSlot {
+                start,
+                daytime: daytime_hours as f64 / (attending.len() * duration) as f64,
+                attendees: attending
+                    .iter()
+                    .map(|((code, _), _)| (*code).clone())
+                    .collect(),
+                absentees: absent
+                    .iter()
+                    .map(|((code, _), _)| (*code).clone())
+                    .collect(),
+                repeats_on_other_days,
+            }
+        })
+        .collect();
+
+    slots.sort_by(|a, b| {
+        b.attendees
+            .len()
+            .cmp(&a.attendees.len())
+            .then(b.daytime.total_cmp(&a.daytime))
+            .then(b.repeats_on_other_days.cmp(&a.repeats_on_other_days))
+            .then(a.start.cmp(&b.start))
+    });
+
+    // Keep the best of any slots that overlap.
+    let mut recommended: Vec<MeetingSlot> = vec![];
+    for slot in slots {
+        if recommended.len() == count {
+            break;
+        }
+        if recommended
+            .iter()
+            .all(|r| slot.start.abs_diff(r.start) >= duration)
+        {
+            recommended.push(slot);
+        }
+    }
+    recommended
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::student_with_hours;
+
+    #[test]
+    fn ranks_slots() {
+        // Monday 9 AM to 1 PM and Wednesday 9 AM to 1 PM, plus Tuesday 2 to 4 AM.
+        let hours = [9, 10, 11, 12, 26, 27, 57, 58, 59, 60];
+        let a = student_with_hours("a", "UTC", &hours).encode();
+        let b = student_with_hours("b", "UTC", &hours).encode();
+        // Only Monday 10 AM to noon.
+        let c = student_with_hours("c", "UTC", &[10, 11]).encode();
+
+        let slots = recommend_meeting_slots(&[a.clone(), b.clone(), c.clone()], 2, 4);
+        assert_eq!(
+            vec![10, 57, 59, 26],
+            slots.iter().map(|s| s.start).collect::<Vec<_>>()
+        );
+        assert_eq!(vec![a.clone(), b.clone(), c.clone()], slots[0].attendees);
+        assert!(slots[0].absentees.is_empty());
+        assert_eq!(1.0, slots[0].daytime);
+
+        // Wednesday at 9 AM also works on Monday, for as many students.
+        assert_eq!(vec![c], slots[1].absentees);
+        assert_eq!(1, slots[1].repeats_on_other_days);
+
+        // The small hours come last.
+        assert_eq!(0.0, slots[3].daytime);
+    }
+
+    #[test]
+    fn daytime_is_local() {
+        // 4 to 6 PM in Tokyo is 7 to 9 AM in UTC.
+        let tokyo = student_with_hours("t", "Asia/Tokyo", &[16, 17]).encode();
+        let slots = recommend_meeting_slots(&[tokyo], 2, 1);
+        assert_eq!(vec![7], slots.iter().map(|s| s.start).collect::<Vec<_>>());
+        assert_eq!(1.0, slots[0].daytime);
+    }
+
+    #[test]
+    fn nothing_to_recommend() {
+        let nobody = student_with_hours("n", "UTC", &[]).encode();
+        assert!(recommend_meeting_slots(std::slice::from_ref(&nobody), 2, 3).is_empty());
+        assert!(recommend_meeting_slots(&[], 2, 3).is_empty());
+        assert!(recommend_meeting_slots(&[nobody], 0, 3).is_empty());
+    }
+}
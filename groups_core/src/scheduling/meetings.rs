@@ -0,0 +1,114 @@
+/// Requirements on how often and how long groups must be able to meet each week, for courses where one good hour
+/// isn't enough, such as teams that must meet twice a week on different days.
+use std::sync::LazyLock;
+
+use serde::{Deserialize, Serialize};
+
+use super::Group;
+use super::availability::WeekBits;
+use crate::constants::{NUM_DAYS_PER_WEEK, NUM_HOURS_PER_DAY};
+
+/// Score taken off a group for each required meeting it can't hold, as a fraction of a perfect group's score. A
+/// perfect group's score is the most any group can score, so a group missing a meeting always scores below any group
+/// that can hold them all.
+pub(crate) const MISSED_MEETING_PENALTY: f64 = 1.0;
+
+/// The hours of each day of the week.
+static DAYS: LazyLock<[WeekBits; NUM_DAYS_PER_WEEK]> = LazyLock::new(|| {
+    std::array::from_fn(|day| {
+        WeekBits::from_hours(day * NUM_HOURS_PER_DAY..(day + 1) * NUM_HOURS_PER_DAY)
+    })
+});
+
+/// Meetings each group must be able to hold every week: `meetings` blocks of at least `min_hours` consecutive hours
+/// when every member is available, each starting on a different day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MeetingRequirement {
+    pub meetings: usize,
+    pub min_hours: usize,
+}
+
+impl Default for MeetingRequirement {
+    fn default() -> Self {
+        MeetingRequirement {
+            meetings: 1,
+            min_hours: 1,
+        }
+    }
+}
+
+impl MeetingRequirement {
+    /// Requires one block of at least `min_hours` consecutive hours.
+    pub fn min_block(min_hours: usize) -> Self {
+        MeetingRequirement {
+            meetings: 1,
+            min_hours,
+        }
+    }
+
+    /// How many of the required meetings a group whose members are all available at `common_hours` (in UTC) can
+    /// hold, and the hours of every block long enough to hold one.
+    pub(crate) fn meetings_possible(&self, common_hours: WeekBits) -> (usize, WeekBits) {
+        let min_hours = self.min_hours.max(1);
+        let starts = common_hours.run_starts(min_hours);
+        let days = DAYS
+            .iter()
+            .filter(|&&day| !(starts & day).is_empty())
+            .count();
+        (days.min(self.meetings), common_hours.in_runs(min_hours))
+    }
+
+    /// Score taken off a group that can hold `meetings_possible` of the required meetings, as a fraction of a perfect
+    /// group's score.
+    pub(crate) fn penalty(&self, meetings_possible: usize) -> f64 {
+        self.meetings.saturating_sub(meetings_possible) as f64 * MISSED_MEETING_PENALTY
+    }
+
+    /// Indices of the groups in `groups` that can't hold every required meeting.
+    pub(crate) fn unmet(&self, groups: &[Group]) -> Vec<usize> {
+        groups
+            .iter()
+            .enumerate()
+            .filter(|(_, group)| self.meetings_possible(group.common_hours()).0 < self.meetings)
+            .map(|(g, _)| g)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_on_distinct_days() {
+        let twice_for_two_hours = MeetingRequirement {
+            meetings: 2,
+            min_hours: 2,
+        };
+        // Monday 9 to 11 AM and 2 to 4 PM, Tuesday at 9 AM, Wednesday 9 AM to noon.
+        let hours = WeekBits::from_hours([9, 10, 14, 15, 33, 57, 58, 59]);
+        let (possible, blocks) = twice_for_two_hours.meetings_possible(hours);
+        assert_eq!(2, possible);
+        assert_eq!(
+            vec![9, 10, 14, 15, 57, 58, 59],
+            blocks.iter_ones().collect::<Vec<_>>()
+        );
+        assert_eq!(0.0, twice_for_two_hours.penalty(possible));
+
+        // Both Monday blocks are on the same day.
+        let monday = WeekBits::from_hours([9, 10, 14, 15, 33]);
+        let (possible, _) = twice_for_two_hours.meetings_possible(monday);
+        assert_eq!(1, possible);
+        assert_eq!(
+            MISSED_MEETING_PENALTY,
+            twice_for_two_hours.penalty(possible)
+        );
+
+        let (possible, blocks) = twice_for_two_hours.meetings_possible(WeekBits::default());
+        assert_eq!((0, true), (possible, blocks.is_empty()));
+        assert_eq!(
+            2.0 * MISSED_MEETING_PENALTY,
+            twice_for_two_hours.penalty(possible)
+        );
+    }
+}
@@ -0,0 +1,336 @@
+/// Assigns each group a mentor, such as the TA they meet with every week. Mentors submit schedule codes like students
+/// do and each mentors at most a set number of groups; a group is only given a mentor who's available when the whole
+/// group is. Mentors can also be part of `SchedulingOptions`, so groups are formed that some mentor can meet with.
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use super::availability::WeekBits;
+use super::{
+    DefaultStrategy, DisplayGroup, Group, GroupSizing, SchedulingOptions, SchedulingStrategy,
+};
+use super::{create_groups_with_options, display_groups, pretty_hours};
+use crate::student::Student;
+
+/// Score taken off a group that no mentor is available to meet with, as a fraction of a perfect group's score.
+pub(crate) const UNMENTORED_PENALTY: f64 = 0.5;
+
+/// A TA or instructor who can mentor groups.
+/// In Javascript this is written as `{ "student": "<encoded Student>", "max_groups": 3 }`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mentor {
+    /// Encoded Student with the mentor's name, timezone and availability.
+    pub student: String,
+    /// Most groups the mentor can take on.
+    pub max_groups: usize,
+}
+
+impl Mentor {
+    /// Hours the mentor is available (in UTC), or None if they can't take on any groups or their code doesn't decode.
+    pub(crate) fn availability(&self) -> Option<WeekBits> {
+        if self.max_groups == 0 {
+            return None;
+        }
+        Student::from_encoded(&self.student).map(|s| WeekBits::from_student(&s))
+    }
+}
+
+/// The mentor given to a group by `assign_mentors`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MentorAssignment {
+    /// Encoded Student of the mentor.
+    pub mentor: String,
+    /// Hours the mentor and the whole group are available (in UTC), or the group's suggested meet times the mentor is
+    /// available at if its members never all are.
+    pub meet_times: Vec<usize>,
+}
+
+/// Groups returned by `create_groups_with_mentors`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GroupsWithMentors {
+    pub groups: Vec<Group>,
+    /// The mentor of each of `groups`, in the same order, or None if no mentor with room to spare can meet with the
+    /// group.
+    pub mentors: Vec<Option<MentorAssignment>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DisplayGroupWithMentor {
+    #[serde(flatten)]
+    group: DisplayGroup,
+    #[serde(flatten)]
+    mentor: DisplayMentor,
+}
+
+/// A group's `MentorAssignment` as passed to Javascript.
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct DisplayMentor {
+    mentor: Option<String>,
+    mentor_meet_times: Vec<String>,
+}
+
+impl DisplayMentor {
+    pub(crate) fn new(assignment: Option<MentorAssignment>, timezone: &str) -> Self {
+        DisplayMentor {
+            mentor_meet_times: assignment
+                .as_ref()
+                .map(|a| pretty_hours(&a.meet_times, timezone))
+                .unwrap_or_default(),
+            mentor: assignment.map(|a| a.mentor),
+        }
+    }
+}
+
+#[wasm_bindgen]
+/// Same as `create_groups_with_mentors`, but suitable for calling from WASM because it takes and returns JSValues.
+/// `students` is a Javascript array of encoded Student (strings).
+/// `sizing` is a `GroupSizing` object, as in `create_groups_wasm`.
+/// `mentors` is a Javascript array of `Mentor` objects: `{ "student": "<encoded Student>", "max_groups": 3 }`.
+/// `output_timezone` is the timezone which will be used when generating the `suggested_meet_times` and
+/// `mentor_meet_times` arrays in each output group.
+/// Returns a Javascript array of groups as from `create_groups_wasm` which also have `mentor` (an encoded Student, or
+/// null) and `mentor_meet_times`.
+pub fn create_groups_with_mentors_wasm(
+    students: JsValue,
+    sizing: JsValue,
+    mentors: JsValue,
+    output_timezone: String,
+) -> JsValue {
+    let student_strings: Vec<String> = serde_wasm_bindgen::from_value(students).unwrap();
+    let sizing: GroupSizing = serde_wasm_bindgen::from_value(sizing).unwrap();
+    let mentors: Vec<Mentor> = serde_wasm_bindgen::from_value(mentors).unwrap();
+
+    let result = create_groups_with_mentors::<DefaultStrategy>(&student_strings, sizing, &mentors);
+    let display: Vec<DisplayGroupWithMentor> = display_groups(&result.groups, &output_timezone)
+        .into_iter()
+        .zip(result.mentors)
+        .map(|(group, assignment)| DisplayGroupWithMentor {
+            group,
+            mentor: DisplayMentor::new(assignment, &output_timezone),
+        })
+        .collect();
+    serde_wasm_bindgen::to_value(&display).unwrap()
+}
+
+/// Like `create_groups`, but looks for groups that one of `mentors` can meet with, then assigns each group a mentor
+/// with `assign_mentors`. To combine mentors with other options, set `SchedulingOptions::mentors` and call
+/// `assign_mentors` on the groups from `create_groups_with_options`.
+pub fn create_groups_with_mentors<S: SchedulingStrategy>(
+    students_encoded: &[String],
+    sizing: impl Into<GroupSizing>,
+    mentors: &[Mentor],
+) -> GroupsWithMentors {
+    let groups = create_groups_with_options::<S>(
+        students_encoded,
+        sizing,
+        SchedulingOptions {
+            mentors: mentors.to_vec(),
+            ..SchedulingOptions::default()
+        },
+    );
+    GroupsWithMentors {
+        mentors: assign_mentors(&groups, mentors),
+        groups,
+    }
+}
+
+/// Assigns each of `groups` a mentor from `mentors` who's available at one or more hours when every member of the
+/// group is (or, for groups whose members never all are, at one of its suggested meet times), without giving any
+/// mentor more than `max_groups` groups. As many groups get a mentor as possible, and groups are given mentors they
+/// share more hours with where that doesn't leave another group without one. Returns the mentor of each group, in the
+/// same order, or None for groups left without one. Mentors whose codes don't decode are never assigned.
+pub fn assign_mentors(groups: &[Group], mentors: &[Mentor]) -> Vec<Option<MentorAssignment>> {
+    let availability: Vec<Option<WeekBits>> = mentors.iter().map(Mentor::availability).collect();
+
+    // The hours each group could meet with each mentor.
+    let joint_hours: Vec<Vec<WeekBits>> = groups
+        .iter()
+        .map(|group| {
+            let group_hours = group.meeting_hours();
+            availability
+                .iter()
+                .map(|hours| hours.map_or(WeekBits::default(), |hours| hours & group_hours))
+                .collect()
+        })
+        .collect();
+    // Mentors each group could meet with, sharing the most hours first.
+    let candidates: Vec<Vec<usize>> = joint_hours
+        .iter()
+        .map(|joint| {
+            let mut candidates: Vec<usize> = (0..mentors.len())
+                .filter(|&m| !joint[m].is_empty())
+                .collect();
+            candidates.sort_by_key(|&m| std::cmp::Reverse(joint[m].count_ones()));
+            candidates
+        })
+        .collect();
+
+    let mut mentored: Vec<Vec<usize>> = vec![vec![]; mentors.len()];
+    for group in 0..groups.len() {
+        let mut tried = vec![false; mentors.len()];
+        assign(group, &candidates, mentors, &mut mentored, &mut tried);
+    }
+
+    let mut assignments = vec![None; groups.len()];
+    for (m, mentored) in mentored.iter().enumerate() {
+        for &group in mentored {
+            assignments[group] = Some(MentorAssignment {
+                mentor: mentors[m].student.clone(),
+                meet_times: joint_hours[group][m].iter_ones().collect(),
+            });
+        }
+    }
+    assignments
+}
+
+/// Gives `group` one of its candidate mentors, moving groups already assigned to a full mentor on to another of their
+/// candidates if that makes room. Returns whether `group` was given a mentor. `tried` marks mentors already considered
+/// while placing this group, so each is only looked at once.
+fn assign(
+    group: usize,
+    candidates: &[Vec<usize>],
+    mentors: &[Mentor],
+    mentored: &mut [Vec<usize>],
+    tried: &mut [bool],
+) -> bool {
+    for &m in &candidates[group] {
+        if tried[m] {
+            continue;
+        }
+        tried[m] = true;
+
+        if mentored[m].len() < mentors[m].max_groups {
+            mentored[m].push(group);
+            return true;
+        }
+        for i in 0..mentored[m].len() {
+            if assign(mentored[m][i], candidates, mentors, mentored, tried) {
+                mentored[m][i] = group;
+                return true;
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::student_with_hours;
+
+    fn group(suggested_meet_times: Vec<usize>) -> Group {
+        Group {
+            students: vec![],
+            suggested_meet_times,
+        }
+    }
+
+    #[test]
+    fn assigns_within_limits() {
+        let x = Mentor {
+            student: student_with_hours("x", "UTC", &[10, 11]).encode(),
+            max_groups: 1,
+        };
+        let y = Mentor {
+            student: student_with_hours("y", "UTC", &[10, 30]).encode(),
+            max_groups: 1,
+        };
+
+        // The first group would rather meet with x, but only y is left for the second if it does.
+        let groups = [group(vec![10, 11]), group(vec![11, 12]), group(vec![50])];
+        let assignments = assign_mentors(&groups, &[x.clone(), y.clone()]);
+        assert_eq!(
+            vec![
+                Some(MentorAssignment {
+                    mentor: y.student.clone(),
+                    meet_times: vec![10],
+                }),
+                Some(MentorAssignment {
+                    mentor: x.student.clone(),
+                    meet_times: vec![11],
+                }),
+                None,
+            ],
+            assignments
+        );
+
+        // With room for both, the first group gets the mentor it shares the most hours with.
+        let roomy_x = Mentor { max_groups: 2, ..x };
+        let assignments = assign_mentors(&groups, &[roomy_x.clone(), y]);
+        assert_eq!(
+            Some(vec![10, 11]),
+            assignments[0].as_ref().map(|a| a.meet_times.clone())
+        );
+        assert!(
+            assignments[..2]
+                .iter()
+                .all(|a| a.as_ref().unwrap().mentor == roomy_x.student)
+        );
+    }
+
+    #[test]
+    fn matches_hours_the_whole_group_shares() {
+        let group = Group {
+            students: vec![
+                student_with_hours("a", "UTC", &[10, 11, 12]).encode(),
+                student_with_hours("b", "UTC", &[10, 11, 12, 13]).encode(),
+            ],
+            suggested_meet_times: vec![10],
+        };
+        let mentor = Mentor {
+            student: student_with_hours("m", "UTC", &[12, 13]).encode(),
+            max_groups: 1,
+        };
+        assert_eq!(
+            vec![Some(MentorAssignment {
+                mentor: mentor.student.clone(),
+                meet_times: vec![12],
+            })],
+            assign_mentors(&[group], &[mentor])
+        );
+    }
+
+    #[test]
+    fn groups_formed_around_mentors() {
+        // Everyone shares 1 to 5 AM, which the mentor can't make. Only a and b also share 8 to noon, and only c and d
+        // share 4 to 8 PM, when the mentor is free.
+        let early = [1, 2, 3, 4];
+        let morning = [8, 9, 10, 11];
+        let evening = [16, 17, 18, 19];
+        let codes = vec![
+            student_with_hours("a", "UTC", &[early, morning].concat()).encode(),
+            student_with_hours("b", "UTC", &[early, morning].concat()).encode(),
+            student_with_hours("c", "UTC", &[early, evening].concat()).encode(),
+            student_with_hours("d", "UTC", &[early, evening].concat()).encode(),
+        ];
+        let mentor = Mentor {
+            student: student_with_hours("m", "UTC", &[morning, evening].concat()).encode(),
+            max_groups: 2,
+        };
+
+        let result = create_groups_with_mentors::<DefaultStrategy>(&codes, 2, &[mentor]);
+        let mut groups: Vec<Vec<String>> =
+            result.groups.iter().map(|g| g.students.clone()).collect();
+        groups.iter_mut().for_each(|g| g.sort_unstable());
+        groups.sort_unstable();
+        let mut expected = vec![codes[..2].to_vec(), codes[2..].to_vec()];
+        expected.sort_unstable();
+        assert_eq!(expected, groups);
+        assert!(result.mentors.iter().all(Option::is_some));
+    }
+
+    #[test]
+    fn no_mentors_to_spare() {
+        let groups = [group(vec![10])];
+        assert_eq!(vec![None], assign_mentors(&groups, &[]));
+
+        let busy = Mentor {
+            student: student_with_hours("busy", "UTC", &[10]).encode(),
+            max_groups: 0,
+        };
+        let unknown = Mentor {
+            student: "not a student".to_string(),
+            max_groups: 5,
+        };
+        assert_eq!(vec![None], assign_mentors(&groups, &[busy, unknown]));
+    }
+}
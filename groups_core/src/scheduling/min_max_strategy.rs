@@ -1,88 +1,73 @@
 use itertools::Itertools;
-use num::Integer;
 use rand::rng;
-use rand::seq::SliceRandom;
 
-use crate::scheduling::{
-    Group, hours_with_n_or_more_available_students, num_students_available_at_hour,
-};
+use crate::scheduling::{Group, Layout, suggested_meet_times};
 use crate::student::Student;
 
-use super::SchedulingStrategy;
+use super::availability::WeekBits;
+use super::{SchedulingOptions, SchedulingStrategy, Seeding};
 
 pub struct MinMaxStrategy;
 
 impl SchedulingStrategy for MinMaxStrategy {
     /// Based on the methodology described in https://www.researchgate.net/publication/258239070_Design_and_validation_of_a_web-based_system_for_assigning_members_to_teams_using_instructor-specified_criteria
     // Algo:
-    // 1. Randomly assign students to teams of size n.
+    // 1. Assign students to teams of size n, randomly unless the options ask for other seeding.
     // 2. Calculate question and complicance scores.
     // 3. Iteratively change team assignments to maximize the minimum compliance score of the set of teams.
-    fn run(students: &[Student], group_size: usize) -> Vec<Group> {
-        if students.is_empty() || group_size < 1 {
+    // Locked students are never part of a swap, and penalties from `options` are taken off each team's score.
+    // Stops early with the best teams so far if the search is cancelled or runs out of time.
+    fn run(students: &[Student], layout: &Layout, options: &SchedulingOptions) -> Vec<Group> {
+        if students.is_empty() {
             return vec![];
         }
 
+        // Each member's availability is converted to UTC once, up front.
+        let availability = students.iter().map(WeekBits::from_student).collect_vec();
+        let team_score =
+            |team: &[usize]| team_sched_score(team, &availability) - options.group_penalty(team);
+
+        let team_ranges = layout.group_ranges().collect_vec();
+        let mut is_movable = vec![false; students.len()];
+        for &position in layout.movable() {
+            is_movable[position] = true;
+        }
+
         const RANDOM_STARTS: usize = 50;
         const TEAM_SWAP_MAX_PASSES: usize = 20;
 
-        let num_teams = Integer::div_ceil(&students.len(), &group_size);
-
         // Start out with the given array. Hopefully we'll generate something better.
-        let mut best_assignment = (0..students.len()).collect_vec();
-        let mut best_assignment_min_score = best_assignment
-            .chunks(group_size)
-            .map(|t| team_sched_score(t, students))
+        let mut best_assignment = layout.initial().to_vec();
+        let mut best_assignment_min_score = layout
+            .groups(&best_assignment)
+            .map(team_score)
             .fold(f64::INFINITY, |a, b| a.min(b));
 
-        for _ in 0..RANDOM_STARTS {
-            let mut teams = (0..students.len()).collect_vec();
-            teams.shuffle(&mut rng());
+        let seeding = options.seeding.unwrap_or(Seeding::Random);
+
+        let clock = options.search.start();
+        for start in 0..RANDOM_STARTS {
+            let mut teams = seeding.arrangement(layout, &availability, &mut rng());
 
             for _ in 0..TEAM_SWAP_MAX_PASSES {
                 let mut swap_happened = false;
 
-                // Unfortunately a bit of index math here because doing the swap upsets the borrow-checker otherwise.
-                for team_a_index in 0..(num_teams - 1) {
-                    let team_a_start_index = team_a_index * group_size;
-                    let team_a_size = if (team_a_start_index + group_size) <= students.len() {
-                        group_size
-                    } else {
-                        students.len() - (team_a_start_index + group_size)
-                    };
-                    for team_b_index in (team_a_index + 1)..num_teams {
-                        let team_b_start_index = team_b_index * group_size;
-                        let team_b_size = if (team_b_start_index + group_size) <= students.len() {
-                            group_size
-                        } else {
-                            students.len() - team_b_start_index
-                        };
-                        for student_a_index in
-                            team_a_start_index..(team_a_start_index + team_a_size)
-                        {
-                            for student_b_index in
-                                team_b_start_index..(team_b_start_index + team_b_size)
-                            {
-                                let old_team_a_score = team_sched_score(
-                                    &teams[team_a_start_index..(team_a_start_index + team_a_size)],
-                                    students,
-                                );
-                                let old_team_b_score = team_sched_score(
-                                    &teams[team_b_start_index..(team_b_start_index + team_b_size)],
-                                    students,
-                                );
+                for (team_a_index, team_a) in team_ranges.iter().enumerate() {
+                    for team_b in &team_ranges[(team_a_index + 1)..] {
+                        for student_a_index in team_a.clone() {
+                            for student_b_index in team_b.clone() {
+                                if !is_movable[student_a_index] || !is_movable[student_b_index] {
+                                    continue;
+                                }
+
+                                let old_team_a_score = team_score(&teams[team_a.clone()]);
+                                let old_team_b_score = team_score(&teams[team_b.clone()]);
                                 let old = old_team_a_score.min(old_team_b_score);
 
                                 teams.swap(student_a_index, student_b_index);
 
-                                let new_team_a_score = team_sched_score(
-                                    &teams[team_a_start_index..(team_a_start_index + team_a_size)],
-                                    students,
-                                );
-                                let new_team_b_score = team_sched_score(
-                                    &teams[team_b_start_index..(team_b_start_index + team_b_size)],
-                                    students,
-                                );
+                                let new_team_a_score = team_score(&teams[team_a.clone()]);
+                                let new_team_b_score = team_score(&teams[team_b.clone()]);
                                 let new = new_team_a_score.min(new_team_b_score);
 
                                 if new > old {
@@ -96,49 +81,34 @@ impl SchedulingStrategy for MinMaxStrategy {
                     }
                 }
 
-                if !swap_happened {
+                if !swap_happened || clock.should_stop() {
                     break;
                 }
             }
 
-            let min_score: f64 = teams
-                .chunks(group_size)
-                .map(|t| team_sched_score(t, students))
+            let min_score: f64 = layout
+                .groups(&teams)
+                .map(team_score)
                 .fold(f64::INFINITY, |a, b| a.min(b));
 
             if min_score > best_assignment_min_score {
                 best_assignment_min_score = min_score;
                 best_assignment = teams;
             }
+
+            clock.report(best_assignment_min_score, None, start + 1, RANDOM_STARTS);
+            if clock.should_stop() {
+                break;
+            }
         }
 
         // Convert best_assignment to Vec<Group>.
-        let mut result = Vec::with_capacity(num_teams);
-        for team in best_assignment.chunks(group_size) {
+        let mut result = Vec::with_capacity(team_ranges.len());
+        for team in layout.groups(&best_assignment) {
             let mut student_ids = team.iter().map(|&i| students[i].encode()).collect_vec();
             student_ids.sort_unstable(); // To make unit testing easier.
 
-            let meet_times = team
-                .iter()
-                .map(|&i| students[i].availability_array_in_utc())
-                .reduce(|accum, item| accum & item)
-                .unwrap();
-            let suggested_meet_times = if meet_times.count_ones() > 0 {
-                // All group members are available at these times.
-                meet_times.iter_ones().collect_vec()
-            } else {
-                // All group members are never available at the same time. Find the maximum number of group
-                // members that are available at all times, and note all the hours that this many group members
-                // are available.
-                let num_students_avail_at_hour = num_students_available_at_hour(team, students);
-                let max_num_students_simultaneously_available =
-                    *num_students_avail_at_hour.iter().max().unwrap();
-
-                hours_with_n_or_more_available_students(
-                    max_num_students_simultaneously_available,
-                    num_students_avail_at_hour,
-                )
-            };
+            let suggested_meet_times = suggested_meet_times(team, students);
 
             result.push(Group {
                 students: student_ids,
@@ -156,14 +126,14 @@ impl SchedulingStrategy for MinMaxStrategy {
 /// This heuristic returns a value on the interval [0, 1], where the value of zero indicates
 /// complete heterogeneity (undesirable: the entire team never is available to meet at the same time) and
 /// a value of one indicates adequate homogeneity (desirable: the entire team has at least h hours to meet in common).
-fn team_sched_score(team: &[usize], students: &[Student]) -> f64 {
+fn team_sched_score(team: &[usize], availability: &[WeekBits]) -> f64 {
     // h is the number of compatible hours beyond which the developers deemed further compatibility unnecessary (h = 40 in Team-Maker Version 1).
     #[allow(non_upper_case_globals)]
     const h: f64 = 40.0;
 
     let anded = team
         .iter()
-        .map(|&s| students[s].availability_array_in_utc())
+        .map(|&s| availability[s])
         .reduce(|accum, item| accum & item)
         .unwrap();
 
@@ -182,6 +152,10 @@ mod tests {
 
     use super::*;
 
+    fn availability(team: &[Student]) -> Vec<WeekBits> {
+        team.iter().map(WeekBits::from_student).collect()
+    }
+
     #[test]
     fn test_team_sched_score_complete_incompatibility() {
         let avail: String = (0..NUM_HOURS_PER_WEEK).map(|_| "0").collect();
@@ -192,7 +166,7 @@ mod tests {
             Student::new("2", tz, &avail).unwrap(),
             Student::new("3", tz, &avail).unwrap(),
         ];
-        let actual = team_sched_score(&[0, 1, 2], &team);
+        let actual = team_sched_score(&[0, 1, 2], &availability(&team));
         assert_eq!(actual, 0.0);
     }
 
@@ -206,7 +180,7 @@ mod tests {
             Student::new("2", tz, &avail).unwrap(),
             Student::new("3", tz, &avail).unwrap(),
         ];
-        let actual = team_sched_score(&[0, 1, 2], &team);
+        let actual = team_sched_score(&[0, 1, 2], &availability(&team));
         assert_eq!(actual, 1.0);
     }
 
@@ -224,7 +198,7 @@ mod tests {
             Student::new("2", tz, &avail).unwrap(),
             Student::new("3", tz, &avail).unwrap(),
         ];
-        let actual = team_sched_score(&[0, 1, 2], &team);
+        let actual = team_sched_score(&[0, 1, 2], &availability(&team));
         assert_eq!(actual, 1.0);
     }
 
@@ -268,7 +242,7 @@ mod tests {
             )
             .unwrap(),
         ];
-        let actual = team_sched_score(&[0, 1, 2, 3], &team);
+        let actual = team_sched_score(&[0, 1, 2, 3], &availability(&team));
 
         // This sample team has 34 time blocks with everyone available. In this case the summation in (3) returns a value of 34,
         // and the score s_sch is given by 34/40 = 0.85, a number close to 1, indicating schedule compatibility.
@@ -292,7 +266,11 @@ mod tests {
         .map(|s| Student::from_encoded(s).unwrap())
         .collect();
 
-        let best_grouping = MinMaxStrategy::run(&students, 2);
+        let best_grouping = MinMaxStrategy::run(
+            &students,
+            &Layout::new(vec![2; 4], &[]).unwrap(),
+            &SchedulingOptions::default(),
+        );
         assert_eq!(best_grouping.len(), 4); // 4 groups of 2.
         assert_eq!(
             best_grouping,
@@ -332,7 +310,11 @@ mod tests {
     #[test]
     fn test_large_random() {
         let (students, seed) = random_students(50, None);
-        let best_grouping = MinMaxStrategy::run(&students, 5);
+        let best_grouping = MinMaxStrategy::run(
+            &students,
+            &Layout::new(vec![5; 10], &[]).unwrap(),
+            &SchedulingOptions::default(),
+        );
 
         let times = best_grouping
             .iter()
@@ -1,20 +1,371 @@
 use crate::constants::NUM_HOURS_PER_WEEK;
 use crate::student::Student;
+use num::Integer;
+use rand::Rng;
+use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::time::Duration;
 use time::OffsetDateTime;
 use time_tz::{Offset, TimeZone, timezones};
 use wasm_bindgen::prelude::*;
 
+pub use self::alternatives::{
+    Alternative, Alternatives, create_alternative_groups, grouping_similarity,
+};
+use self::availability::{HourCounts, WeekBits};
+use self::clustering_strategy::ClusteringStrategy;
+pub use self::evaluation::{Evaluation, GroupEvaluation, evaluate_groups};
 use self::hillclimbing_strategy::HillClimbingStrategy;
+use self::hillclimbing_strategy::Problem;
+pub use self::history::{PartnerHistory, RepeatPartnerRules};
+pub use self::meeting_slots::{MeetingSlot, recommend_meeting_slots};
+pub use self::meetings::MeetingRequirement;
+use self::mentors::DisplayMentor;
+pub use self::mentors::{
+    GroupsWithMentors, Mentor, MentorAssignment, assign_mentors, create_groups_with_mentors,
+};
+pub use self::night::NightRules;
+pub use self::pareto::{ParetoGrouping, create_pareto_groups};
+pub use self::repair::{Repair, StudentMove, repair_groups};
+pub use self::report::{GroupReport, GroupingReport, GroupingStats, MemberCoverage, report_groups};
+use self::rooms::DisplayRoom;
+pub use self::rooms::{
+    GroupsWithRooms, Room, RoomBooking, RoomRequirement, book_rooms, create_groups_with_rooms,
+};
+pub use self::rotation::{Rotation, create_rotation};
+use self::search::optimality_gap;
+pub use self::search::{Cancellation, Progress, ProgressCallback, SearchControl};
+pub use self::seeding::Seeding;
+pub use self::session::SchedulingSession;
+pub use self::what_if::{WhatIf, what_if_moves};
 
+mod alternatives;
+mod availability;
+mod clustering_strategy;
+mod evaluation;
 mod hillclimbing_strategy;
+mod history;
+mod meeting_slots;
+mod meetings;
+mod mentors;
 mod min_max_strategy;
+mod night;
+mod pareto;
+mod repair;
+mod report;
+mod rooms;
+mod rotation;
+mod search;
+mod seeding;
+mod session;
+mod what_if;
 
-type DefaultStrategy = HillClimbingStrategy;
+/// Classes with at least this many students are grouped by `ClusteringStrategy`. It finds groups as good as hill
+/// climbing from random starting points in a fraction of the time (about 90 ms instead of 440 ms for 1000 students).
+pub(crate) const LARGE_CLASS_SIZE: usize = 500;
+
+/// Hill climbing, or clustering for large classes.
+struct DefaultStrategy;
+
+impl SchedulingStrategy for DefaultStrategy {
+    fn run(students: &[Student], layout: &Layout, options: &SchedulingOptions) -> Vec<Group> {
+        if students.len() >= LARGE_CLASS_SIZE {
+            ClusteringStrategy::run(students, layout, options)
+        } else {
+            HillClimbingStrategy::run(students, layout, options)
+        }
+    }
+
+    fn run_alternatives(
+        students: &[Student],
+        layout: &Layout,
+        options: &SchedulingOptions,
+        count: usize,
+        max_similarity: f64,
+    ) -> Vec<Vec<Group>> {
+        if students.len() >= LARGE_CLASS_SIZE {
+            ClusteringStrategy::run_alternatives(students, layout, options, count, max_similarity)
+        } else {
+            HillClimbingStrategy::run_alternatives(students, layout, options, count, max_similarity)
+        }
+    }
+}
 
 /// A trait representing a specific scheduler for groups.
 pub trait SchedulingStrategy {
-    fn run(students: &[Student], group_size: usize) -> Vec<Group>;
+    /// Divides `students` into the groups described by `layout`, which must have been built for `students.len()`
+    /// students. Locked students start out in their group and are never moved by the strategy.
+    fn run(students: &[Student], layout: &Layout, options: &SchedulingOptions) -> Vec<Group>;
+
+    /// Like `run`, but returns up to `count` of the best groupings found, best first, each at most `max_similarity`
+    /// similar to every better one (see `grouping_similarity`). Identical groupings are only returned once.
+    /// Strategies that only keep their best grouping return just that.
+    fn run_alternatives(
+        students: &[Student],
+        layout: &Layout,
+        options: &SchedulingOptions,
+        _count: usize,
+        _max_similarity: f64,
+    ) -> Vec<Vec<Group>> {
+        let groups = Self::run(students, layout, options);
+        if groups.is_empty() {
+            vec![]
+        } else {
+            vec![groups]
+        }
+    }
+}
+
+/// Adjustments to how strategies score groups and how long they search. The default leaves each strategy's own
+/// scoring unchanged and lets it search until done.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SchedulingOptions {
+    /// Which students have worked together before.
+    pub partner_history: PartnerHistory,
+    /// How strongly to avoid regrouping students in `partner_history`.
+    pub repeat_partners: RepeatPartnerRules,
+    /// Time budget, cancellation and progress reporting for the search.
+    pub search: SearchControl,
+    /// How each restart arranges students before searching. None leaves it to the strategy: most constrained students
+    /// first for hill climbing, by similarity for clustering and random for MinMax.
+    pub seeding: Option<Seeding>,
+    /// Which hours are night in each student's own timezone, and how strongly to avoid meeting then. None doesn't
+    /// treat any hour differently. Only strategies that climb from `HillClimbingStrategy`'s scoring use this.
+    pub night: Option<NightRules>,
+    /// Meetings every group must be able to hold each week. None only asks that groups can meet at all. Only
+    /// strategies that climb from `HillClimbingStrategy`'s scoring use this: hill climbing and clustering, so also
+    /// `DefaultStrategy`.
+    pub meetings: Option<MeetingRequirement>,
+    /// Mentors, such as TAs, one of whom should be able to meet with each group. Groups no mentor is available to meet
+    /// with are penalized, and groups are only suggested hours a mentor can join. Empty doesn't ask for any. How many
+    /// groups each mentor takes on is left to `assign_mentors`. Only strategies that climb from
+    /// `HillClimbingStrategy`'s scoring use this.
+    pub mentors: Vec<Mentor>,
+    /// Rooms groups should meet in. Groups that can't all meet for the required hours while any room is open are
+    /// penalized, and groups that can are only suggested those hours. None doesn't ask for a room. How many groups
+    /// fit in each room at once is left to `book_rooms`. Only strategies that climb from `HillClimbingStrategy`'s
+    /// scoring use this.
+    pub rooms: Option<RoomRequirement>,
+}
+
+impl SchedulingOptions {
+    /// Score taken off `group` by these options, as a fraction of a perfect group's score.
+    pub(crate) fn group_penalty(&self, group: &[usize]) -> f64 {
+        self.partner_history.penalty(group, &self.repeat_partners)
+    }
+}
+
+/// How the number and size of groups is chosen. Group sizes are always balanced to differ by at most one.
+/// In Javascript this is written as `{ "size": 5 }` or `{ "count": 4 }`. A bare number, as callers passed before
+/// groups could be counted, is read as a size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", from = "GroupSizingInput")]
+pub enum GroupSizing {
+    /// As few groups as possible with at most this many students each.
+    Size(usize),
+    /// Exactly this many groups, however many students that puts in each.
+    Count(usize),
+}
+
+/// What `GroupSizing` is read from: either form it's written in.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum GroupSizingInput {
+    Size(usize),
+    Tagged(TaggedGroupSizing),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TaggedGroupSizing {
+    Size(usize),
+    Count(usize),
+}
+
+impl From<GroupSizingInput> for GroupSizing {
+    fn from(input: GroupSizingInput) -> Self {
+        match input {
+            GroupSizingInput::Size(size)
+            | GroupSizingInput::Tagged(TaggedGroupSizing::Size(size)) => GroupSizing::Size(size),
+            GroupSizingInput::Tagged(TaggedGroupSizing::Count(count)) => GroupSizing::Count(count),
+        }
+    }
+}
+
+impl GroupSizing {
+    /// Number of students in each group when `num_students` students are divided this way. Returns None if the
+    /// size is zero or there are fewer students than requested groups.
+    pub fn group_sizes(&self, num_students: usize) -> Option<Vec<usize>> {
+        match *self {
+            GroupSizing::Size(size) => balanced_group_sizes(num_students, 1, size),
+            GroupSizing::Count(count) => sizes_for_group_count(num_students, count, 1),
+        }
+    }
+}
+
+impl From<usize> for GroupSizing {
+    fn from(group_size: usize) -> Self {
+        GroupSizing::Size(group_size)
+    }
+}
+
+/// Splits `num_students` students into as few groups of at most `max_size` as possible, with group sizes that
+/// differ by at most one (for example 11 students with a maximum of 5 gives 4, 4 and 3). Larger groups come first.
+/// Returns None if `max_size` is zero, `min_size` is larger than `max_size`, or balancing would leave a group
+/// smaller than `min_size`.
+pub fn balanced_group_sizes(
+    num_students: usize,
+    min_size: usize,
+    max_size: usize,
+) -> Option<Vec<usize>> {
+    if max_size == 0 || min_size > max_size {
+        return None;
+    }
+
+    let num_groups = Integer::div_ceil(&num_students, &max_size);
+    sizes_for_group_count(num_students, num_groups, min_size)
+}
+
+/// Splits `num_students` students into exactly `num_groups` groups whose sizes differ by at most one, or None if
+/// that leaves a group smaller than `min_size`.
+fn sizes_for_group_count(
+    num_students: usize,
+    num_groups: usize,
+    min_size: usize,
+) -> Option<Vec<usize>> {
+    if num_groups == 0 {
+        return (num_students == 0).then(Vec::new);
+    }
+
+    let (base, remainder) = num_students.div_rem(&num_groups);
+    if base < min_size.max(1) {
+        return None;
+    }
+
+    Some(
+        (0..num_groups)
+            .map(|g| base + usize::from(g < remainder))
+            .collect(),
+    )
+}
+
+/// The starting arrangement of students shared by the strategies. Positions in the arrangement are split into
+/// consecutive groups of `group_sizes`, and positions holding a locked student can't be changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    /// Number of students in each group.
+    group_sizes: Vec<usize>,
+    /// Student index at each position.
+    initial: Vec<usize>,
+    /// Positions the strategies are free to rearrange.
+    movable: Vec<usize>,
+}
+
+impl Layout {
+    /// Lays out students in groups of `group_sizes` (see `balanced_group_sizes`); the total of `group_sizes` is the
+    /// number of students. Each entry of `locked_groups` holds indices of students that must be placed together in
+    /// a group of their own. An entry may fill a whole group, in which case that group is left untouched.
+    /// Returns None if a group size is zero or `locked_groups` can't be honored: an index is out of range or
+    /// repeated, there are more locked groups than groups, or a locked group is larger than any group left for it.
+    pub fn new(group_sizes: Vec<usize>, locked_groups: &[Vec<usize>]) -> Option<Layout> {
+        if group_sizes.contains(&0) || locked_groups.len() > group_sizes.len() {
+            return None;
+        }
+
+        let num_students: usize = group_sizes.iter().sum();
+        let mut is_locked = vec![false; num_students];
+        for &student in locked_groups.iter().flatten() {
+            if student >= num_students || is_locked[student] {
+                return None;
+            }
+            is_locked[student] = true;
+        }
+
+        // Pair the largest locked groups with the largest groups to give every locked group the best chance of
+        // fitting.
+        let starts: Vec<usize> = group_sizes
+            .iter()
+            .scan(0, |start, size| {
+                let this_start = *start;
+                *start += size;
+                Some(this_start)
+            })
+            .collect();
+        let mut groups_by_size: Vec<usize> = (0..group_sizes.len()).collect();
+        groups_by_size.sort_by_key(|&g| std::cmp::Reverse(group_sizes[g]));
+        let mut locked_by_len: Vec<&Vec<usize>> = locked_groups.iter().collect();
+        locked_by_len.sort_by_key(|l| std::cmp::Reverse(l.len()));
+
+        let mut initial = vec![usize::MAX; num_students];
+        let mut fixed = vec![false; num_students];
+        for (&group, locked) in groups_by_size.iter().zip(locked_by_len) {
+            if locked.len() > group_sizes[group] {
+                return None;
+            }
+
+            for (offset, &student) in locked.iter().enumerate() {
+                initial[starts[group] + offset] = student;
+                fixed[starts[group] + offset] = true;
+            }
+        }
+
+        let movable: Vec<usize> = (0..num_students).filter(|&p| !fixed[p]).collect();
+        let unlocked_students = (0..num_students).filter(|&s| !is_locked[s]);
+        for (&position, student) in movable.iter().zip(unlocked_students) {
+            initial[position] = student;
+        }
+
+        Some(Layout {
+            group_sizes,
+            initial,
+            movable,
+        })
+    }
+
+    /// Number of students in each group.
+    pub fn group_sizes(&self) -> &[usize] {
+        &self.group_sizes
+    }
+
+    /// Student index at each position before any rearranging.
+    pub(crate) fn initial(&self) -> &[usize] {
+        &self.initial
+    }
+
+    /// Positions the strategies are free to rearrange, in increasing order.
+    pub(crate) fn movable(&self) -> &[usize] {
+        &self.movable
+    }
+
+    /// The range of positions making up each group.
+    pub(crate) fn group_ranges(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        self.group_sizes.iter().scan(0, |start, size| {
+            let range = *start..(*start + size);
+            *start += size;
+            Some(range)
+        })
+    }
+
+    /// Splits an arrangement of students into its groups.
+    pub(crate) fn groups<'a>(
+        &'a self,
+        arrangement: &'a [usize],
+    ) -> impl Iterator<Item = &'a [usize]> {
+        self.group_ranges().map(|range| &arrangement[range])
+    }
+
+    /// Returns the initial arrangement with the unlocked students randomly shuffled amongst the movable positions.
+    pub(crate) fn shuffled<R: Rng>(&self, rng: &mut R) -> Vec<usize> {
+        let mut arrangement = self.initial.clone();
+        let mut unlocked: Vec<usize> = self.movable.iter().map(|&p| arrangement[p]).collect();
+        unlocked.shuffle(rng);
+        for (&position, student) in self.movable.iter().zip(unlocked) {
+            arrangement[position] = student;
+        }
+        arrangement
+    }
 }
 
 /// A group of students, along with suggested meet times.
@@ -30,17 +381,42 @@ pub struct Group {
 }
 
 impl Group {
+    /// Hours every member of the group is available (in UTC). Empty if no member's code decodes.
+    fn common_hours(&self) -> WeekBits {
+        self.students
+            .iter()
+            .filter_map(|code| Student::from_encoded(code))
+            .map(|s| WeekBits::from_student(&s))
+            .reduce(|common, hours| common & hours)
+            .unwrap_or_default()
+    }
+
+    /// Hours the whole group can meet (in UTC), or its suggested meet times if its members are never all available.
+    fn meeting_hours(&self) -> WeekBits {
+        let common = self.common_hours();
+        if common.is_empty() {
+            WeekBits::from_hours(self.suggested_meet_times.iter().copied())
+        } else {
+            common
+        }
+    }
+
+    /// Fraction of the group's students available at the best of its suggested meet times, or zero if there are no
+    /// suggested times.
     fn percent_at_suggested_times(&self) -> f64 {
-        let students: Vec<Student> = self
+        if self.students.is_empty() {
+            return 0.0;
+        }
+
+        let suggested = WeekBits::from_hours(self.suggested_meet_times.iter().copied());
+        let available_at_suggested: Vec<WeekBits> = self
             .students
             .iter()
             .filter_map(|s| Student::from_encoded(s))
+            .map(|s| WeekBits::from_student(&s) & suggested)
             .collect();
-
-        let group: Vec<usize> = (0..self.students.len()).collect();
-
-        let avail = num_students_available_at_hour(&group, &students);
-        avail[self.suggested_meet_times[0]] as f64 / self.students.len() as f64
+        let (most_available, _) = HourCounts::new(&available_at_suggested).max();
+        most_available as f64 / self.students.len() as f64
     }
 }
 
@@ -54,37 +430,645 @@ struct DisplayGroup {
 #[wasm_bindgen]
 /// Same as `create_groups`, but suitable for calling from WASM because it takes and returns JSValues.
 /// `students` is a Javascript array of encoded Student (strings).
+/// `sizing` is a `GroupSizing` object: `{ "size": n }` for groups of at most n students or `{ "count": n }` for
+/// exactly n groups. A plain number n is the same as `{ "size": n }`.
 /// `output_timezone` is the timezone which will be used when generating the `suggested_meet_times` array in
 /// each output group.
 /// Returns a Javascript array of JSON objects representing groups.
-pub fn create_groups_wasm(
+pub fn create_groups_wasm(students: JsValue, sizing: JsValue, output_timezone: String) -> JsValue {
+    let student_strings: Vec<String> = serde_wasm_bindgen::from_value(students).unwrap();
+    let sizing: GroupSizing = serde_wasm_bindgen::from_value(sizing).unwrap();
+
+    //  For WASM, the default strategy is the MinMax strategy.
+    let groups = create_groups_default_strategy(&student_strings, sizing);
+    let display = display_groups(&groups, &output_timezone);
+    serde_wasm_bindgen::to_value(&display).unwrap()
+}
+
+#[wasm_bindgen]
+/// Same as `create_groups_with_size_bounds`, but suitable for calling from WASM because it takes and returns JSValues.
+/// `students` is a Javascript array of encoded Student (strings).
+/// `output_timezone` is the timezone which will be used when generating the `suggested_meet_times` array in
+/// each output group.
+/// Returns a Javascript array of JSON objects representing groups, or null if no balanced grouping fits the bounds.
+pub fn create_groups_with_size_bounds_wasm(
     students: JsValue,
-    group_size: usize,
+    min_size: usize,
+    max_size: usize,
     output_timezone: String,
 ) -> JsValue {
     let student_strings: Vec<String> = serde_wasm_bindgen::from_value(students).unwrap();
 
-    //  For WASM, the default strategy is the MinMax strategy.
-    let groups = create_groups_default_strategy(&student_strings, group_size);
+    let display =
+        create_groups_with_size_bounds::<DefaultStrategy>(&student_strings, min_size, max_size)
+            .map(|groups| display_groups(&groups, &output_timezone));
+    serde_wasm_bindgen::to_value(&display).unwrap()
+}
+
+#[wasm_bindgen]
+/// Same as `create_groups_with_locked_students`, but suitable for calling from WASM because it takes and returns JSValues.
+/// `students` is a Javascript array of encoded Student (strings).
+/// `locked_groups` is a Javascript array of arrays of encoded Student, one inner array per locked group.
+/// `sizing` is a `GroupSizing` object, as in `create_groups_wasm`.
+/// `output_timezone` is the timezone which will be used when generating the `suggested_meet_times` array in
+/// each output group.
+/// Returns a Javascript array of JSON objects representing groups, or null if the locked groups can't be honored.
+pub fn create_groups_with_locked_students_wasm(
+    students: JsValue,
+    locked_groups: JsValue,
+    sizing: JsValue,
+    output_timezone: String,
+) -> JsValue {
+    let student_strings: Vec<String> = serde_wasm_bindgen::from_value(students).unwrap();
+    let locked_strings: Vec<Vec<String>> = serde_wasm_bindgen::from_value(locked_groups).unwrap();
+    let sizing: GroupSizing = serde_wasm_bindgen::from_value(sizing).unwrap();
+
+    let display = create_groups_with_locked_students::<DefaultStrategy>(
+        &student_strings,
+        sizing,
+        &locked_strings,
+    )
+    .map(|groups| display_groups(&groups, &output_timezone));
+    serde_wasm_bindgen::to_value(&display).unwrap()
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DisplayGroupsWithMeetings {
+    groups: Vec<DisplayGroup>,
+    unmet: Vec<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DisplayGroupsWithHistory {
+    groups: Vec<DisplayGroup>,
+    repeat_pairings: usize,
+}
+
+#[wasm_bindgen]
+/// Same as `create_groups_with_history`, but suitable for calling from WASM because it takes and returns JSValues.
+/// `students` is a Javascript array of encoded Student (strings).
+/// `sizing` is a `GroupSizing` object, as in `create_groups_wasm`.
+/// `previous_groupings` is a Javascript array of earlier groupings, each an array of arrays of encoded Student.
+/// `rules` is a `RepeatPartnerRules` object: `{ "penalty": 0.5, "max_times_together": 1 }` (`max_times_together`
+/// may be null).
+/// `output_timezone` is the timezone which will be used when generating the `suggested_meet_times` array in
+/// each output group.
+/// Returns a JSON object with `groups` (as from `create_groups_wasm`) and `repeat_pairings`.
+pub fn create_groups_with_history_wasm(
+    students: JsValue,
+    sizing: JsValue,
+    previous_groupings: JsValue,
+    rules: JsValue,
+    output_timezone: String,
+) -> JsValue {
+    let student_strings: Vec<String> = serde_wasm_bindgen::from_value(students).unwrap();
+    let sizing: GroupSizing = serde_wasm_bindgen::from_value(sizing).unwrap();
+    let previous: Vec<Vec<Vec<String>>> =
+        serde_wasm_bindgen::from_value(previous_groupings).unwrap();
+    let rules: RepeatPartnerRules = serde_wasm_bindgen::from_value(rules).unwrap();
+
+    let result =
+        create_groups_with_history::<DefaultStrategy>(&student_strings, sizing, &previous, rules);
+    let display = DisplayGroupsWithHistory {
+        groups: display_groups(&result.groups, &output_timezone),
+        repeat_pairings: result.repeat_pairings,
+    };
+    serde_wasm_bindgen::to_value(&display).unwrap()
+}
+
+#[wasm_bindgen]
+/// Same as `create_groups_with_search`, but suitable for calling from WASM because it takes and returns JSValues.
+/// `students` is a Javascript array of encoded Student (strings).
+/// `sizing` is a `GroupSizing` object, as in `create_groups_wasm`.
+/// `time_budget_ms` is how long to search for, in milliseconds, or undefined to search until done.
+/// `on_progress` is an optional function called with `{ best_score, upper_bound, gap, restarts_completed,
+/// total_restarts, elapsed_ms }` as the search goes. `upper_bound` and `gap` are null if the strategy doesn't know a
+/// bound.
+/// `output_timezone` is the timezone which will be used when generating the `suggested_meet_times` array in
+/// each output group.
+/// Returns a Javascript array of JSON objects representing groups.
+pub fn create_groups_with_time_budget_wasm(
+    students: JsValue,
+    sizing: JsValue,
+    time_budget_ms: Option<f64>,
+    on_progress: Option<js_sys::Function>,
+    output_timezone: String,
+) -> JsValue {
+    let student_strings: Vec<String> = serde_wasm_bindgen::from_value(students).unwrap();
+    let sizing: GroupSizing = serde_wasm_bindgen::from_value(sizing).unwrap();
+
+    let search = SearchControl {
+        time_budget: time_budget_ms.map(|ms| Duration::from_secs_f64(ms.max(0.0) / 1000.0)),
+        progress: ProgressCallback::from_js(on_progress),
+        ..SearchControl::default()
+    };
+    let groups = create_groups_with_search::<DefaultStrategy>(&student_strings, sizing, search);
     let display = display_groups(&groups, &output_timezone);
     serde_wasm_bindgen::to_value(&display).unwrap()
 }
 
-/// Returns the best grouping of students, given the total students in the class and
-/// the maximum size of a group.
+/// Returns the best grouping of students, given the total students in the class and either the maximum size of a
+/// group or the number of groups (a plain `usize` is a maximum size). Group sizes are balanced to differ by at most
+/// one. Returns no groups if `sizing` can't be met, such as asking for more groups than there are students.
 pub fn create_groups<S: SchedulingStrategy>(
     students_encoded: &[String],
-    group_size: usize,
+    sizing: impl Into<GroupSizing>,
+) -> Vec<Group> {
+    create_groups_with_search::<S>(students_encoded, sizing, SearchControl::default())
+}
+
+/// Like `create_groups`, but stops searching when `search` runs out of time or is cancelled and returns the best
+/// grouping found so far, reporting progress along the way.
+pub fn create_groups_with_search<S: SchedulingStrategy>(
+    students_encoded: &[String],
+    sizing: impl Into<GroupSizing>,
+    search: SearchControl,
 ) -> Vec<Group> {
     let students: Vec<Student> = students_encoded
         .iter()
         .filter_map(|s| Student::from_encoded(s))
         .collect();
-    S::run(&students, group_size)
+    let Some(sizes) = sizing.into().group_sizes(students.len()) else {
+        return vec![];
+    };
+    let options = SchedulingOptions {
+        search,
+        ..SchedulingOptions::default()
+    };
+    match Layout::new(sizes, &[]) {
+        Some(layout) => S::run(&students, &layout, &options),
+        None => vec![],
+    }
+}
+
+#[wasm_bindgen]
+/// Same as `create_groups_with_meetings`, but suitable for calling from WASM because it takes and returns JSValues.
+/// `students` is a Javascript array of encoded Student (strings).
+/// `sizing` is a `GroupSizing` object, as in `create_groups_wasm`.
+/// `requirement` is a `MeetingRequirement` object: `{ "meetings": 2, "min_hours": 2 }`. A minimum meeting length is
+/// `{ "meetings": 1, "min_hours": n }`.
+/// `output_timezone` is the timezone which will be used when generating the `suggested_meet_times` array in
+/// each output group.
+/// Returns a JSON object with `groups` (as from `create_groups_wasm`) and `unmet`, the indices of groups that can't hold
+/// every required meeting.
+pub fn create_groups_with_meetings_wasm(
+    students: JsValue,
+    sizing: JsValue,
+    requirement: JsValue,
+    output_timezone: String,
+) -> JsValue {
+    let student_strings: Vec<String> = serde_wasm_bindgen::from_value(students).unwrap();
+    let sizing: GroupSizing = serde_wasm_bindgen::from_value(sizing).unwrap();
+    let requirement: MeetingRequirement = serde_wasm_bindgen::from_value(requirement).unwrap();
+
+    let result =
+        create_groups_with_meetings::<DefaultStrategy>(&student_strings, sizing, requirement);
+    let display = DisplayGroupsWithMeetings {
+        groups: display_groups(&result.groups, &output_timezone),
+        unmet: result.unmet,
+    };
+    serde_wasm_bindgen::to_value(&display).unwrap()
+}
+
+/// Groups returned by `create_groups_with_meetings`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GroupsWithMeetings {
+    pub groups: Vec<Group>,
+    /// Indices into `groups` of the groups that can't hold every required meeting.
+    pub unmet: Vec<usize>,
+}
+
+/// Like `create_groups`, but looks for groups that can each hold the meetings `requirement` asks for every week, and
+/// suggests meet times in blocks long enough for one of them. Strategies that climb from `HillClimbingStrategy`'s
+/// scoring treat groups that can't as infeasible: they score below every group that can. They're still returned, with
+/// the hours the most members are available as usual, and listed in `unmet`.
+pub fn create_groups_with_meetings<S: SchedulingStrategy>(
+    students_encoded: &[String],
+    sizing: impl Into<GroupSizing>,
+    requirement: MeetingRequirement,
+) -> GroupsWithMeetings {
+    let groups = create_groups_with_options::<S>(
+        students_encoded,
+        sizing,
+        SchedulingOptions {
+            meetings: Some(requirement),
+            ..SchedulingOptions::default()
+        },
+    );
+    GroupsWithMeetings {
+        unmet: requirement.unmet(&groups),
+        groups,
+    }
 }
 
-fn create_groups_default_strategy(students_encoded: &[String], group_size: usize) -> Vec<Group> {
-    create_groups::<DefaultStrategy>(students_encoded, group_size)
+/// Like `create_groups_with_meetings`, requiring only that every group shares a block of at least `min_hours`
+/// consecutive hours. Overlaps of scattered or shorter runs of hours don't count.
+pub fn create_groups_with_min_block<S: SchedulingStrategy>(
+    students_encoded: &[String],
+    sizing: impl Into<GroupSizing>,
+    min_hours: usize,
+) -> GroupsWithMeetings {
+    create_groups_with_meetings::<S>(
+        students_encoded,
+        sizing,
+        MeetingRequirement::min_block(min_hours),
+    )
+}
+
+#[wasm_bindgen]
+/// Same as `create_groups_avoiding_night`, but suitable for calling from WASM because it takes and returns JSValues.
+/// `students` is a Javascript array of encoded Student (strings).
+/// `sizing` is a `GroupSizing` object, as in `create_groups_wasm`.
+/// `rules` is a `NightRules` object: `{ "start": 23, "end": 7, "penalty": 0.5, "hard": false }`.
+/// `output_timezone` is the timezone which will be used when generating the `suggested_meet_times` array in
+/// each output group.
+/// Returns a Javascript array of JSON objects representing groups.
+pub fn create_groups_avoiding_night_wasm(
+    students: JsValue,
+    sizing: JsValue,
+    rules: JsValue,
+    output_timezone: String,
+) -> JsValue {
+    let student_strings: Vec<String> = serde_wasm_bindgen::from_value(students).unwrap();
+    let sizing: GroupSizing = serde_wasm_bindgen::from_value(sizing).unwrap();
+    let rules: NightRules = serde_wasm_bindgen::from_value(rules).unwrap();
+
+    let groups = create_groups_avoiding_night::<DefaultStrategy>(&student_strings, sizing, rules);
+    let display = display_groups(&groups, &output_timezone);
+    serde_wasm_bindgen::to_value(&display).unwrap()
+}
+
+/// Like `create_groups`, but avoids groups meeting at night in any member's own timezone as `rules` asks, both when
+/// scoring groups and when suggesting meet times.
+pub fn create_groups_avoiding_night<S: SchedulingStrategy>(
+    students_encoded: &[String],
+    sizing: impl Into<GroupSizing>,
+    rules: NightRules,
+) -> Vec<Group> {
+    create_groups_with_options::<S>(
+        students_encoded,
+        sizing,
+        SchedulingOptions {
+            night: Some(rules),
+            ..SchedulingOptions::default()
+        },
+    )
+}
+
+/// The `SchedulingOptions` `create_groups_with_options_wasm` takes from Javascript. Every field may be left out.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct OptionsInput {
+    seeding: Option<Seeding>,
+    night: Option<NightRules>,
+    meetings: Option<MeetingRequirement>,
+    mentors: Vec<Mentor>,
+    rooms: Option<RoomRequirement>,
+    time_budget_ms: Option<f64>,
+}
+
+impl From<OptionsInput> for SchedulingOptions {
+    fn from(input: OptionsInput) -> Self {
+        SchedulingOptions {
+            seeding: input.seeding,
+            night: input.night,
+            meetings: input.meetings,
+            mentors: input.mentors,
+            rooms: input.rooms,
+            search: SearchControl {
+                time_budget: input
+                    .time_budget_ms
+                    .map(|ms| Duration::from_secs_f64(ms.max(0.0) / 1000.0)),
+                ..SearchControl::default()
+            },
+            ..SchedulingOptions::default()
+        }
+    }
+}
+
+#[wasm_bindgen]
+/// Same as `create_groups_with_options`, but suitable for calling from WASM because it takes and returns JSValues.
+/// `students` is a Javascript array of encoded Student (strings).
+/// `sizing` is a `GroupSizing` object, as in `create_groups_wasm`.
+/// `options` is an object with any of `seeding` (`"random"`, `"most_constrained"` or `"similarity"`), `night` (a
+/// `NightRules` object, as in `create_groups_avoiding_night_wasm`), `meetings` (a `MeetingRequirement` object, as in
+/// `create_groups_with_meetings_wasm`), `mentors` (an array of `Mentor` objects, as in
+/// `create_groups_with_mentors_wasm`), `rooms` (a `RoomRequirement` object: `{ "rooms": [<Room>, ...], "hours": 2 }`,
+/// with `Room` objects as in `create_groups_with_rooms_wasm`) and `time_budget_ms`. Left out options don't change the
+/// search.
+/// `output_timezone` is the timezone which will be used when generating the `suggested_meet_times` array in
+/// each output group.
+/// Returns a Javascript array of groups as from `create_groups_wasm`. With `mentors`, each group is also assigned one
+/// and has `mentor` and `mentor_meet_times` as from `create_groups_with_mentors_wasm`. With `rooms`, each group is also
+/// booked a room and has `room` and `room_start` as from `create_groups_with_rooms_wasm`.
+pub fn create_groups_with_options_wasm(
+    students: JsValue,
+    sizing: JsValue,
+    options: JsValue,
+    output_timezone: String,
+) -> JsValue {
+    let student_strings: Vec<String> = serde_wasm_bindgen::from_value(students).unwrap();
+    let sizing: GroupSizing = serde_wasm_bindgen::from_value(sizing).unwrap();
+    let options: SchedulingOptions = serde_wasm_bindgen::from_value::<OptionsInput>(options)
+        .unwrap()
+        .into();
+
+    let groups =
+        create_groups_with_options::<DefaultStrategy>(&student_strings, sizing, options.clone());
+    let mentors: Vec<Option<DisplayMentor>> = if options.mentors.is_empty() {
+        groups.iter().map(|_| None).collect()
+    } else {
+        assign_mentors(&groups, &options.mentors)
+            .into_iter()
+            .map(|assignment| Some(DisplayMentor::new(assignment, &output_timezone)))
+            .collect()
+    };
+    let rooms: Vec<Option<DisplayRoom>> = match &options.rooms {
+        Some(requirement) => book_rooms(&groups, &requirement.rooms, requirement.hours)
+            .into_iter()
+            .map(|booking| Some(DisplayRoom::new(booking, &output_timezone)))
+            .collect(),
+        None => groups.iter().map(|_| None).collect(),
+    };
+    let display: Vec<DisplayGroupWithOptions> = display_groups(&groups, &output_timezone)
+        .into_iter()
+        .zip(mentors)
+        .zip(rooms)
+        .map(|((group, mentor), room)| DisplayGroupWithOptions {
+            group,
+            mentor,
+            room,
+        })
+        .collect();
+    serde_wasm_bindgen::to_value(&display).unwrap()
+}
+
+/// A group from `create_groups_with_options_wasm`, with what the options it was created with ask for.
+#[derive(Serialize, Debug)]
+struct DisplayGroupWithOptions {
+    #[serde(flatten)]
+    group: DisplayGroup,
+    #[serde(flatten)]
+    mentor: Option<DisplayMentor>,
+    #[serde(flatten)]
+    room: Option<DisplayRoom>,
+}
+
+/// Like `create_groups`, but searches with any combination of `options`, such as seeding, night rules and a meeting
+/// requirement at once. Options that refer to students by index, like `partner_history`, count only the students in
+/// `students_encoded` that decode, in order.
+pub fn create_groups_with_options<S: SchedulingStrategy>(
+    students_encoded: &[String],
+    sizing: impl Into<GroupSizing>,
+    options: SchedulingOptions,
+) -> Vec<Group> {
+    let students: Vec<Student> = students_encoded
+        .iter()
+        .filter_map(|s| Student::from_encoded(s))
+        .collect();
+    match sizing
+        .into()
+        .group_sizes(students.len())
+        .and_then(|sizes| Layout::new(sizes, &[]))
+    {
+        Some(layout) => S::run(&students, &layout, &options),
+        None => vec![],
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DisplayGroupsWithBound {
+    groups: Vec<DisplayGroup>,
+    score: f64,
+    upper_bound: f64,
+    gap: f64,
+}
+
+#[wasm_bindgen]
+/// Same as `create_groups_with_bound`, but suitable for calling from WASM because it takes and returns JSValues.
+/// `students` is a Javascript array of encoded Student (strings).
+/// `sizing` is a `GroupSizing` object, as in `create_groups_wasm`.
+/// `time_budget_ms` is how long to search for, in milliseconds, or undefined to search until done.
+/// `output_timezone` is the timezone which will be used when generating the `suggested_meet_times` array in
+/// each output group.
+/// Returns a JSON object with `groups` (as from `create_groups_wasm`), `score`, `upper_bound` and `gap`.
+pub fn create_groups_with_bound_wasm(
+    students: JsValue,
+    sizing: JsValue,
+    time_budget_ms: Option<f64>,
+    output_timezone: String,
+) -> JsValue {
+    let student_strings: Vec<String> = serde_wasm_bindgen::from_value(students).unwrap();
+    let sizing: GroupSizing = serde_wasm_bindgen::from_value(sizing).unwrap();
+
+    let search = SearchControl {
+        time_budget: time_budget_ms.map(|ms| Duration::from_secs_f64(ms.max(0.0) / 1000.0)),
+        ..SearchControl::default()
+    };
+    let result = create_groups_with_bound::<DefaultStrategy>(&student_strings, sizing, search);
+    let display = DisplayGroupsWithBound {
+        groups: display_groups(&result.groups, &output_timezone),
+        score: result.score,
+        upper_bound: result.upper_bound,
+        gap: result.gap(),
+    };
+    serde_wasm_bindgen::to_value(&display).unwrap()
+}
+
+/// Groups returned by `create_groups_with_bound`.
+#[derive(Debug, PartialEq)]
+pub struct GroupsWithBound {
+    pub groups: Vec<Group>,
+    /// Score of `groups`, scored the way `HillClimbingStrategy` scores them whichever strategy made them.
+    pub score: f64,
+    /// A score no grouping of the class can beat.
+    pub upper_bound: f64,
+}
+
+impl GroupsWithBound {
+    /// How far `score` is below `upper_bound`, as a fraction of the bound: 0 when the groups are known to be optimal.
+    /// The bound comes from a relaxation, so a gap above zero doesn't mean a better grouping exists.
+    pub fn gap(&self) -> f64 {
+        optimality_gap(self.score, self.upper_bound)
+    }
+}
+
+/// Like `create_groups_with_search`, but also reports how close the groups are to the best any grouping of the class
+/// could score. Strategies stop searching early if they find groups scoring the upper bound.
+pub fn create_groups_with_bound<S: SchedulingStrategy>(
+    students_encoded: &[String],
+    sizing: impl Into<GroupSizing>,
+    search: SearchControl,
+) -> GroupsWithBound {
+    let (codes, students): (Vec<&String>, Vec<Student>) = students_encoded
+        .iter()
+        .filter_map(|s| Some((s, Student::from_encoded(s)?)))
+        .unzip();
+    let Some(layout) = sizing
+        .into()
+        .group_sizes(students.len())
+        .and_then(|sizes| Layout::new(sizes, &[]))
+    else {
+        return GroupsWithBound {
+            groups: vec![],
+            score: 0.0,
+            upper_bound: 0.0,
+        };
+    };
+
+    let options = SchedulingOptions {
+        search,
+        ..SchedulingOptions::default()
+    };
+    let groups = S::run(&students, &layout, &options);
+
+    let problem = Problem::new(students, layout, SchedulingOptions::default());
+    let score: isize = group_indices(&codes, &groups)
+        .iter()
+        .map(|group| problem.score_group(group).0)
+        .sum();
+    GroupsWithBound {
+        groups,
+        score: score as f64,
+        upper_bound: problem.upper_bound as f64,
+    }
+}
+
+/// Groups returned by `create_groups_with_history`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GroupsWithHistory {
+    pub groups: Vec<Group>,
+    /// Number of pairs of students placed in the same group who had already worked together.
+    pub repeat_pairings: usize,
+}
+
+/// Like `create_groups`, but avoids grouping students who have already worked together in `previous_groupings`
+/// (each a list of groups of encoded Student, such as earlier projects this term) according to `rules`.
+/// Students in earlier groupings who aren't in `students_encoded` are ignored.
+pub fn create_groups_with_history<S: SchedulingStrategy>(
+    students_encoded: &[String],
+    sizing: impl Into<GroupSizing>,
+    previous_groupings: &[Vec<Vec<String>>],
+    rules: RepeatPartnerRules,
+) -> GroupsWithHistory {
+    let (codes, students): (Vec<&String>, Vec<Student>) = students_encoded
+        .iter()
+        .filter_map(|s| Some((s, Student::from_encoded(s)?)))
+        .unzip();
+
+    let mut index_of_code = HashMap::new();
+    for (i, &code) in codes.iter().enumerate() {
+        index_of_code.entry(code.as_str()).or_insert(i);
+    }
+    let to_indices = |groups: &[Vec<String>]| -> Vec<Vec<usize>> {
+        groups
+            .iter()
+            .map(|g| {
+                g.iter()
+                    .filter_map(|code| index_of_code.get(code.as_str()).copied())
+                    .collect()
+            })
+            .collect()
+    };
+
+    let history = PartnerHistory::new(
+        &previous_groupings
+            .iter()
+            .map(|grouping| to_indices(grouping))
+            .collect::<Vec<_>>(),
+    );
+
+    let groups = sizing
+        .into()
+        .group_sizes(students.len())
+        .and_then(|sizes| Layout::new(sizes, &[]))
+        .map(|layout| {
+            let options = SchedulingOptions {
+                partner_history: history.clone(),
+                repeat_partners: rules,
+                ..SchedulingOptions::default()
+            };
+            S::run(&students, &layout, &options)
+        })
+        .unwrap_or_default();
+
+    let final_groups: Vec<Vec<String>> = groups.iter().map(|g| g.students.clone()).collect();
+    let repeat_pairings = to_indices(&final_groups)
+        .iter()
+        .map(|g| history.repeat_pairs(g))
+        .sum();
+
+    GroupsWithHistory {
+        groups,
+        repeat_pairings,
+    }
+}
+
+/// Like `create_groups`, but every group must have between `min_size` and `max_size` students. Uses as few groups
+/// as `max_size` allows, balanced to differ in size by at most one. Returns None if that leaves a group smaller than
+/// `min_size` (for example 11 students with sizes 4 to 5) or the bounds are invalid.
+pub fn create_groups_with_size_bounds<S: SchedulingStrategy>(
+    students_encoded: &[String],
+    min_size: usize,
+    max_size: usize,
+) -> Option<Vec<Group>> {
+    let students: Vec<Student> = students_encoded
+        .iter()
+        .filter_map(|s| Student::from_encoded(s))
+        .collect();
+    let sizes = balanced_group_sizes(students.len(), min_size, max_size)?;
+    let layout = Layout::new(sizes, &[])?;
+    Some(S::run(&students, &layout, &SchedulingOptions::default()))
+}
+
+/// Like `create_groups`, but the students in each entry of `locked_groups` are kept together in a group of their own,
+/// and only the remaining students are rearranged. An entry may be a complete group, which is then left as is.
+/// Every locked student must also appear in `students_encoded`. Returns None if the locked groups can't be honored
+/// (a locked student is unknown or locked twice, there are more locked groups than groups, or a locked group is
+/// too large).
+pub fn create_groups_with_locked_students<S: SchedulingStrategy>(
+    students_encoded: &[String],
+    sizing: impl Into<GroupSizing>,
+    locked_groups: &[Vec<String>],
+) -> Option<Vec<Group>> {
+    let (codes, students): (Vec<&String>, Vec<Student>) = students_encoded
+        .iter()
+        .filter_map(|s| Some((s, Student::from_encoded(s)?)))
+        .unzip();
+
+    let locked_indices = locked_indices(&codes, locked_groups)?;
+
+    // Like `create_groups`, sizing that can't be met gives no groups rather than a locking error.
+    let Some(sizes) = sizing.into().group_sizes(students.len()) else {
+        return Some(vec![]);
+    };
+    let layout = Layout::new(sizes, &locked_indices)?;
+    Some(S::run(&students, &layout, &SchedulingOptions::default()))
+}
+
+/// Converts groups of encoded students into indices into `codes`. Returns None if a code isn't in `codes`, or is
+/// locked more times than it appears there.
+fn locked_indices(codes: &[&String], locked_groups: &[Vec<String>]) -> Option<Vec<Vec<usize>>> {
+    let mut claimed = vec![false; codes.len()];
+    let mut locked_indices = Vec::with_capacity(locked_groups.len());
+    for locked in locked_groups {
+        let mut indices = Vec::with_capacity(locked.len());
+        for code in locked {
+            // Match the first unclaimed copy, so a code pasted in twice can still be locked twice.
+            let index = (0..codes.len()).find(|&i| !claimed[i] && codes[i] == code)?;
+            claimed[index] = true;
+            indices.push(index);
+        }
+        locked_indices.push(indices);
+    }
+    Some(locked_indices)
+}
+
+fn create_groups_default_strategy(
+    students_encoded: &[String],
+    sizing: impl Into<GroupSizing>,
+) -> Vec<Group> {
+    create_groups::<DefaultStrategy>(students_encoded, sizing)
 }
 
 fn display_groups(groups: &[Group], timezone: &str) -> Vec<DisplayGroup> {
@@ -98,6 +1082,25 @@ fn display_groups(groups: &[Group], timezone: &str) -> Vec<DisplayGroup> {
         .collect()
 }
 
+/// Indices into `codes` of the students in each of `groups`. A code listed more than once in `codes` is matched to a
+/// different index each time it appears in `groups`, and codes not in `codes` are left out.
+fn group_indices(codes: &[&String], groups: &[Group]) -> Vec<Vec<usize>> {
+    let mut indices_of_code: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, code) in codes.iter().enumerate().rev() {
+        indices_of_code.entry(code.as_str()).or_default().push(i);
+    }
+
+    groups
+        .iter()
+        .map(|g| {
+            g.students
+                .iter()
+                .filter_map(|code| indices_of_code.get_mut(code.as_str())?.pop())
+                .collect()
+        })
+        .collect()
+}
+
 /// Returns a count for each hour in UTC in a week, where the count is the number of students available at that hour.
 fn num_students_available_at_hour(
     group: &[usize],
@@ -120,19 +1123,14 @@ fn num_students_available_at_hour(
     result
 }
 
-fn hours_with_n_or_more_available_students(
-    n: u32,
-    availabilities: [u32; NUM_HOURS_PER_WEEK],
-) -> Vec<usize> {
-    availabilities
+/// Hours in UTC when every member of `team` is available, or if there are none, the hours when the most members are.
+pub(crate) fn suggested_meet_times(team: &[usize], students: &[Student]) -> Vec<usize> {
+    let availability: Vec<_> = team
         .iter()
-        .enumerate()
-        .filter_map(
-            |(hour, &student_count)| {
-                if student_count >= n { Some(hour) } else { None }
-            },
-        )
-        .collect()
+        .map(|&i| WeekBits::from_student(&students[i]))
+        .collect();
+    let (_, hours) = HourCounts::new(&availability).max();
+    hours.iter_ones().collect()
 }
 
 fn pretty_hours(hours_in_utc: &[usize], output_timezone: &str) -> Vec<String> {
@@ -181,6 +1179,7 @@ fn pretty_hours(hours_in_utc: &[usize], output_timezone: &str) -> Vec<String> {
 #[cfg(test)]
 mod tests {
     use super::{min_max_strategy::MinMaxStrategy, *};
+    use crate::random::{random_students, student_with_hours};
 
     #[test]
     fn groups_no_students() {
@@ -254,4 +1253,372 @@ mod tests {
         let groups = create_groups::<MinMaxStrategy>(&students, 5);
         assert_eq!(2, groups.len())
     }
+
+    /// Pairs of students with identical availability, pairs sharing no availability with any other pair.
+    fn paired_students() -> Vec<String> {
+        [
+            "VGVzdDF8QWZyaWNhL0FiaWRqYW58MTkyMHwwfDB8MHwwfDA=",
+            "VGVzdDJ8QWZyaWNhL0FiaWRqYW58MTkyMHwwfDB8MHwwfDA=",
+            "VGVzdDN8QWZyaWNhL0FiaWRqYW58MzA3MjB8MHwwfDB8MHww",
+            "VGVzdDR8QWZyaWNhL0FiaWRqYW58MzA3MjB8MHwwfDB8MHww",
+            "VGVzdDV8QWZyaWNhL0FiaWRqYW58NDkxNTIwfDB8MHwwfDB8MA==",
+            "VGVzdDZ8QWZyaWNhL0FiaWRqYW58NDkxNTIwfDB8MHwwfDB8MA==",
+            "VGVzdDd8QWZyaWNhL0FiaWRqYW58Nzg2NDMyMHwwfDB8MHwwfDA=",
+            "VGVzdDh8QWZyaWNhL0FiaWRqYW58Nzg2NDMyMHwwfDB8MHwwfDA=",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect()
+    }
+
+    fn sorted(codes: &[String]) -> Vec<String> {
+        let mut codes = codes.to_vec();
+        codes.sort_unstable();
+        codes
+    }
+
+    #[test]
+    fn locked_students_stay_together() {
+        let students = paired_students();
+
+        // Lock two students that don't share any availability together, and another into a group on their own.
+        let locked = vec![
+            vec![students[0].clone(), students[2].clone()],
+            vec![students[4].clone()],
+        ];
+
+        for groups in [
+            create_groups_with_locked_students::<MinMaxStrategy>(&students, 2, &locked).unwrap(),
+            create_groups_with_locked_students::<HillClimbingStrategy>(&students, 2, &locked)
+                .unwrap(),
+        ] {
+            assert_eq!(4, groups.len());
+            assert!(
+                groups
+                    .iter()
+                    .any(|g| sorted(&g.students) == sorted(&locked[0]))
+            );
+            assert!(groups.iter().any(|g| g.students.contains(&students[4])));
+        }
+    }
+
+    #[test]
+    fn locked_students_partial_group_is_completed() {
+        let students = paired_students();
+        let locked = vec![vec![students[6].clone()]];
+
+        let groups =
+            create_groups_with_locked_students::<HillClimbingStrategy>(&students, 2, &locked)
+                .unwrap();
+        let group = groups
+            .iter()
+            .find(|g| g.students.contains(&students[6]))
+            .unwrap();
+        assert_eq!(sorted(&group.students), sorted(&students[6..8]));
+    }
+
+    #[test]
+    fn locked_students_invalid() {
+        let students = paired_students();
+
+        // Too large for a group.
+        let too_large = vec![students[..3].to_vec()];
+        assert_eq!(
+            None,
+            create_groups_with_locked_students::<DefaultStrategy>(&students, 2, &too_large)
+        );
+
+        // More locked groups than groups.
+        let too_many: Vec<_> = students[..5].iter().map(|s| vec![s.clone()]).collect();
+        assert_eq!(
+            None,
+            create_groups_with_locked_students::<DefaultStrategy>(&students, 2, &too_many)
+        );
+
+        // Locked in two places.
+        let repeated = vec![vec![students[0].clone()], vec![students[0].clone()]];
+        assert_eq!(
+            None,
+            create_groups_with_locked_students::<DefaultStrategy>(&students, 2, &repeated)
+        );
+
+        // Not in the class.
+        let unknown = vec![vec![String::from(
+            "ZGZzZGZzfEFmcmljYS9BbGdpZXJzfDE5MjB8MjE0NzQ4Mzc2OHw3fDB8MHww",
+        )]];
+        assert_eq!(
+            None,
+            create_groups_with_locked_students::<DefaultStrategy>(&students, 2, &unknown)
+        );
+    }
+
+    #[test]
+    fn balanced_sizes_every_remainder() {
+        // 10 through 14 students with at most 5 per group covers every remainder.
+        assert_eq!(balanced_group_sizes(10, 1, 5), Some(vec![5, 5]));
+        assert_eq!(balanced_group_sizes(11, 1, 5), Some(vec![4, 4, 3]));
+        assert_eq!(balanced_group_sizes(12, 1, 5), Some(vec![4, 4, 4]));
+        assert_eq!(balanced_group_sizes(13, 1, 5), Some(vec![5, 4, 4]));
+        assert_eq!(balanced_group_sizes(14, 1, 5), Some(vec![5, 5, 4]));
+    }
+
+    #[test]
+    fn balanced_sizes_small_classes() {
+        assert_eq!(balanced_group_sizes(0, 1, 5), Some(vec![]));
+        assert_eq!(balanced_group_sizes(1, 1, 5), Some(vec![1]));
+        assert_eq!(balanced_group_sizes(6, 1, 5), Some(vec![3, 3]));
+    }
+
+    #[test]
+    fn balanced_sizes_min_bound() {
+        assert_eq!(balanced_group_sizes(11, 3, 5), Some(vec![4, 4, 3]));
+        assert_eq!(balanced_group_sizes(11, 4, 5), None);
+        assert_eq!(balanced_group_sizes(2, 3, 5), None);
+    }
+
+    #[test]
+    fn balanced_sizes_invalid_bounds() {
+        assert_eq!(balanced_group_sizes(10, 1, 0), None);
+        assert_eq!(balanced_group_sizes(10, 4, 3), None);
+    }
+
+    #[test]
+    fn strategies_balance_group_sizes() {
+        let (students, _) = random_students(11, Some(11));
+        let codes: Vec<String> = students.iter().map(|s| s.encode()).collect();
+
+        for groups in [
+            create_groups::<MinMaxStrategy>(&codes, 5),
+            create_groups::<HillClimbingStrategy>(&codes, 5),
+        ] {
+            let mut sizes: Vec<usize> = groups.iter().map(|g| g.students.len()).collect();
+            sizes.sort_unstable();
+            assert_eq!(sizes, vec![3, 4, 4]);
+        }
+    }
+
+    #[test]
+    fn sizing_by_count() {
+        assert_eq!(GroupSizing::Count(3).group_sizes(11), Some(vec![4, 4, 3]));
+        assert_eq!(GroupSizing::Count(2).group_sizes(11), Some(vec![6, 5]));
+        assert_eq!(GroupSizing::Count(11).group_sizes(11), Some(vec![1; 11]));
+        assert_eq!(GroupSizing::Count(12).group_sizes(11), None);
+        assert_eq!(GroupSizing::Count(0).group_sizes(11), None);
+        assert_eq!(GroupSizing::Count(0).group_sizes(0), Some(vec![]));
+    }
+
+    #[test]
+    fn sizing_from_number_or_object() {
+        use serde::de::IntoDeserializer;
+        use serde::de::value::{Error, MapDeserializer};
+
+        let bare = GroupSizing::deserialize(IntoDeserializer::<Error>::into_deserializer(5u64));
+        assert_eq!(Ok(GroupSizing::Size(5)), bare);
+        let size = GroupSizing::deserialize(MapDeserializer::<_, Error>::new(
+            [("size", 5u64)].into_iter(),
+        ));
+        assert_eq!(Ok(GroupSizing::Size(5)), size);
+        let count = GroupSizing::deserialize(MapDeserializer::<_, Error>::new(
+            [("count", 4u64)].into_iter(),
+        ));
+        assert_eq!(Ok(GroupSizing::Count(4)), count);
+    }
+
+    #[test]
+    fn options_from_javascript() {
+        use serde::de::IntoDeserializer;
+        use serde::de::value::{Error, MapDeserializer};
+
+        let none = OptionsInput::deserialize(MapDeserializer::<_, Error>::new(
+            std::iter::empty::<(&str, &str)>(),
+        ))
+        .unwrap();
+        let none = SchedulingOptions::from(none);
+        assert_eq!(
+            (None, None, None),
+            (none.seeding, none.night, none.meetings)
+        );
+        assert_eq!(None, none.search.time_budget);
+
+        let seeding = Seeding::deserialize(IntoDeserializer::<Error>::into_deserializer(
+            "most_constrained",
+        ));
+        assert_eq!(Ok(Seeding::MostConstrained), seeding);
+    }
+
+    #[test]
+    fn options_combine() {
+        let codes = paired_students();
+        let options = SchedulingOptions {
+            seeding: Some(Seeding::Similarity),
+            meetings: Some(MeetingRequirement::min_block(1)),
+            night: Some(NightRules::default()),
+            ..SchedulingOptions::default()
+        };
+        let groups = create_groups_with_options::<DefaultStrategy>(&codes, 2, options);
+        assert_eq!(codes.len(), groups.iter().map(|g| g.students.len()).sum());
+        assert!(groups.iter().all(|g| !g.suggested_meet_times.is_empty()));
+    }
+
+    #[test]
+    fn create_groups_by_count() {
+        let codes = paired_students();
+
+        let groups = create_groups_default_strategy(&codes, GroupSizing::Count(3));
+        let mut sizes: Vec<usize> = groups.iter().map(|g| g.students.len()).collect();
+        sizes.sort_unstable();
+        assert_eq!(sizes, vec![2, 3, 3]);
+
+        assert!(create_groups_default_strategy(&codes, GroupSizing::Count(9)).is_empty());
+    }
+
+    #[test]
+    fn size_bounds_rejected() {
+        let codes = paired_students();
+        assert_eq!(
+            None,
+            create_groups_with_size_bounds::<DefaultStrategy>(&codes[..7], 3, 3)
+        );
+        assert_eq!(
+            Some(3),
+            create_groups_with_size_bounds::<DefaultStrategy>(&codes[..7], 2, 3).map(|g| g.len())
+        );
+    }
+
+    #[test]
+    fn history_avoids_previous_partners() {
+        let students = paired_students();
+        let previous: Vec<Vec<String>> = students.chunks(2).map(|pair| pair.to_vec()).collect();
+
+        // Without history the best pairs are the same as last time.
+        let repeated = create_groups_with_history::<DefaultStrategy>(
+            &students,
+            2,
+            std::slice::from_ref(&previous),
+            RepeatPartnerRules {
+                penalty: 0.0,
+                max_times_together: None,
+            },
+        );
+        assert_eq!(4, repeated.repeat_pairings);
+
+        let rules = RepeatPartnerRules {
+            penalty: 0.5,
+            max_times_together: Some(1),
+        };
+        for result in [
+            create_groups_with_history::<MinMaxStrategy>(
+                &students,
+                2,
+                std::slice::from_ref(&previous),
+                rules,
+            ),
+            create_groups_with_history::<HillClimbingStrategy>(
+                &students,
+                2,
+                std::slice::from_ref(&previous),
+                rules,
+            ),
+        ] {
+            assert_eq!(4, result.groups.len());
+            assert_eq!(0, result.repeat_pairings);
+            for group in &result.groups {
+                assert!(!previous.contains(&sorted(&group.students)));
+            }
+        }
+    }
+
+    #[test]
+    fn reports_gap_to_upper_bound() {
+        let (students, _) = random_students(40, Some(40));
+        let codes: Vec<String> = students.iter().map(|s| s.encode()).collect();
+
+        let result =
+            create_groups_with_bound::<DefaultStrategy>(&codes, 5, SearchControl::default());
+        assert_eq!(8, result.groups.len());
+        assert!(result.score > 0.0);
+        assert!(result.score <= result.upper_bound);
+        assert!((0.0..=1.0).contains(&result.gap()));
+
+        // Sizing that can't be met gives no groups and nothing to be far from.
+        let result =
+            create_groups_with_bound::<DefaultStrategy>(&codes, 0, SearchControl::default());
+        assert!(result.groups.is_empty());
+        assert_eq!(0.0, result.gap());
+    }
+
+    #[test]
+    fn groups_hold_required_meetings() {
+        // Monday and Wednesday 9 to 11 AM, or only Monday.
+        let codes = vec![
+            student_with_hours("a", "UTC", &[9, 10, 57, 58]).encode(),
+            student_with_hours("b", "UTC", &[9, 10]).encode(),
+            student_with_hours("c", "UTC", &[9, 10, 57, 58]).encode(),
+            student_with_hours("d", "UTC", &[9, 10]).encode(),
+        ];
+        let requirement = MeetingRequirement {
+            meetings: 2,
+            min_hours: 2,
+        };
+
+        let result = create_groups_with_meetings::<HillClimbingStrategy>(&codes, 2, requirement);
+        let twice = result
+            .groups
+            .iter()
+            .position(|g| g.students.contains(&codes[0]))
+            .unwrap();
+        assert_eq!(
+            sorted(&[codes[0].clone(), codes[2].clone()]),
+            result.groups[twice].students
+        );
+        assert_eq!(
+            vec![9, 10, 57, 58],
+            result.groups[twice].suggested_meet_times
+        );
+        assert_eq!(vec![1 - twice], result.unmet);
+
+        // Everyone can hold one two hour meeting, but scattered hours aren't enough.
+        assert!(
+            create_groups_with_min_block::<HillClimbingStrategy>(&codes, 2, 2)
+                .unmet
+                .is_empty()
+        );
+        let scattered = [
+            student_with_hours("e", "UTC", &[9, 11, 13]).encode(),
+            student_with_hours("f", "UTC", &[9, 11, 13]).encode(),
+        ];
+        let result = create_groups_with_min_block::<HillClimbingStrategy>(&scattered, 2, 2);
+        assert_eq!(vec![0], result.unmet);
+    }
+
+    #[test]
+    fn percent_at_suggested_times_uses_best_time() {
+        let codes = paired_students();
+        let students: Vec<Student> = codes
+            .iter()
+            .filter_map(|c| Student::from_encoded(c))
+            .collect();
+        let group = |members: &[usize], suggested_meet_times: Vec<usize>| Group {
+            students: members.iter().map(|&i| codes[i].clone()).collect(),
+            suggested_meet_times,
+        };
+
+        let pair = group(&[0, 1], suggested_meet_times(&[0, 1], &students));
+        assert_eq!(1.0, pair.percent_at_suggested_times());
+
+        // The suggested times are when either of two students who share no hours is available.
+        let apart = group(&[0, 2], suggested_meet_times(&[0, 2], &students));
+        assert_eq!(0.5, apart.percent_at_suggested_times());
+
+        assert_eq!(0.0, group(&[0, 1], vec![]).percent_at_suggested_times());
+        assert_eq!(0.0, group(&[], vec![]).percent_at_suggested_times());
+    }
+
+    #[test]
+    fn layout_fits_largest_locked_group_in_largest_group() {
+        // 9 students in groups of 5 and 4. The 5 locked students must take the first group.
+        let layout = Layout::new(vec![5, 4], &[vec![0, 1, 2, 3], vec![4, 5, 6, 7, 8]]).unwrap();
+        assert_eq!(layout.initial(), &[4, 5, 6, 7, 8, 0, 1, 2, 3]);
+        assert!(layout.movable().is_empty());
+    }
 }
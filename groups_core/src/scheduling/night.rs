@@ -0,0 +1,91 @@
+/// Keeps groups from meeting in the middle of the night for any of their members. Scoring is done in UTC, so a group
+/// spread across timezones can otherwise look perfect at hours that are 3 AM for someone, for example a student who
+/// marked those hours by mistake.
+use serde::{Deserialize, Serialize};
+
+use super::availability::WeekBits;
+use crate::constants::{NUM_DAYS_PER_WEEK, NUM_HOURS_PER_DAY, NUM_HOURS_PER_WEEK};
+use crate::student::Student;
+
+/// Which hours are night, in each student's own timezone, and how strongly to avoid meeting then.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NightRules {
+    /// Local hour nights start at, from 0 to 23.
+    pub start: usize,
+    /// Local hour nights end at, from 0 to 23. Nights run past midnight when this is at or before `start`.
+    pub end: usize,
+    /// Score taken off a group if all its members would be meeting at night at every hour they could meet, as a
+    /// fraction of a perfect group's score. Groups are suggested the hours that are night for the fewest members, and
+    /// lose that share of this. Ignored when `hard` is set.
+    pub penalty: f64,
+    /// Treat students as unavailable at night, so groups are never scored on or suggested night hours.
+    pub hard: bool,
+}
+
+impl Default for NightRules {
+    fn default() -> Self {
+        NightRules {
+            start: 23,
+            end: 7,
+            penalty: 0.5,
+            hard: false,
+        }
+    }
+}
+
+impl NightRules {
+    /// Hours of the week that are night where `student` lives, in UTC.
+    pub(crate) fn night_hours(&self, student: &Student) -> WeekBits {
+        let offset = student.utc_offset_hours() as isize;
+        let length =
+            (self.end + NUM_HOURS_PER_DAY - self.start % NUM_HOURS_PER_DAY) % NUM_HOURS_PER_DAY;
+        let length = if length == 0 {
+            NUM_HOURS_PER_DAY
+        } else {
+            length
+        };
+
+        WeekBits::from_hours((0..NUM_DAYS_PER_WEEK).flat_map(|day| {
+            (0..length).map(move |h| {
+                let local = (day * NUM_HOURS_PER_DAY + self.start + h) as isize;
+                (local - offset).rem_euclid(NUM_HOURS_PER_WEEK as isize) as usize
+            })
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn student(timezone: &str) -> Student {
+        Student::new("s", timezone, &"1".repeat(NUM_HOURS_PER_WEEK)).unwrap()
+    }
+
+    #[test]
+    fn nights_in_local_time() {
+        let rules = NightRules::default();
+        let utc = rules.night_hours(&student("UTC"));
+        assert_eq!(8 * 7, utc.count_ones());
+        assert!(utc.contains(23) && utc.contains(6) && !utc.contains(7) && !utc.contains(22));
+        // Sunday night runs into Monday morning.
+        assert!(utc.contains(167) && utc.contains(0));
+
+        // 11 PM in Tokyo is 2 PM in UTC.
+        let tokyo = rules.night_hours(&student("Asia/Tokyo"));
+        assert_eq!(8 * 7, tokyo.count_ones());
+        assert!(
+            tokyo.contains(14) && tokyo.contains(21) && !tokyo.contains(22) && !tokyo.contains(13)
+        );
+
+        let short_nights = NightRules {
+            start: 1,
+            end: 5,
+            ..rules
+        };
+        assert_eq!(
+            4 * 7,
+            short_nights.night_hours(&student("UTC")).count_ones()
+        );
+    }
+}
@@ -0,0 +1,313 @@
+/// Groupings that trade off how much time groups share against how evenly good the groups are. Maximizing the total
+/// score can leave a few groups that can barely meet while the rest meet easily, so instead of one best grouping this
+/// finds the Pareto front over three objectives: total shared hours, the worst group's score and inequality between
+/// groups. Climbs aim for different objectives, and the groupings no other grouping beats on all three are kept.
+use std::cmp::Reverse;
+
+use rand::rng;
+use rand::seq::IndexedRandom;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use super::hillclimbing_strategy::{
+    Assignment, NUM_TRIES_FOR_BETTER_NEIGHBOR, Problem, TopAssignments, climb_in_batches,
+};
+use super::search::SearchClock;
+use super::{DisplayGroup, Group, GroupSizing, Layout, SchedulingOptions, Seeding, display_groups};
+use crate::student::Student;
+
+// More starting points than when looking for the single best grouping, since each climb only finds one point of the
+// front.
+const NUM_STARTING_POINTS: usize = 120;
+
+/// One of the groupings returned by `create_pareto_groups`.
+#[derive(Debug, PartialEq)]
+pub struct ParetoGrouping {
+    pub groups: Vec<Group>,
+    /// Sum over groups of the hours every student in the group is available. Higher is better.
+    pub total_shared_hours: usize,
+    /// Lowest score of any group, per student in it, so groups of different sizes compare. Scored the way
+    /// `HillClimbingStrategy` scores groups. Higher is better.
+    pub worst_group_score: f64,
+    /// Gini coefficient of the score per student of each student's group, from 0 (every group as good) towards 1.
+    /// Lower is better.
+    pub inequality: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DisplayParetoGrouping {
+    groups: Vec<DisplayGroup>,
+    total_shared_hours: usize,
+    worst_group_score: f64,
+    inequality: f64,
+}
+
+#[wasm_bindgen]
+/// Same as `create_pareto_groups`, but suitable for calling from WASM because it takes and returns JSValues.
+/// `students` is a Javascript array of encoded Student (strings).
+/// `sizing` is a `GroupSizing` object, as in `create_groups_wasm`.
+/// `output_timezone` is the timezone which will be used when generating the `suggested_meet_times` array in
+/// each output group.
+/// Returns a Javascript array of `{ groups, total_shared_hours, worst_group_score, inequality }` objects (`groups` as
+/// from `create_groups_wasm`), most total shared hours first.
+pub fn create_pareto_groups_wasm(
+    students: JsValue,
+    sizing: JsValue,
+    output_timezone: String,
+) -> JsValue {
+    let student_strings: Vec<String> = serde_wasm_bindgen::from_value(students).unwrap();
+    let sizing: GroupSizing = serde_wasm_bindgen::from_value(sizing).unwrap();
+
+    let display = create_pareto_groups(&student_strings, sizing)
+        .iter()
+        .map(|p| DisplayParetoGrouping {
+            groups: display_groups(&p.groups, &output_timezone),
+            total_shared_hours: p.total_shared_hours,
+            worst_group_score: p.worst_group_score,
+            inequality: p.inequality,
+        })
+        .collect::<Vec<_>>();
+    serde_wasm_bindgen::to_value(&display).unwrap()
+}
+
+/// Like `create_groups`, but returns groupings trading total shared hours against fairness between groups, so an
+/// instructor can pick the trade-off. No returned grouping is at least as good as another on every objective of
+/// `ParetoGrouping`. Sorted by most total shared hours first. Returns no groupings if `sizing` can't be met.
+pub fn create_pareto_groups(
+    students_encoded: &[String],
+    sizing: impl Into<GroupSizing>,
+) -> Vec<ParetoGrouping> {
+    let students: Vec<Student> = students_encoded
+        .iter()
+        .filter_map(|s| Student::from_encoded(s))
+        .collect();
+    let Some(layout) = sizing
+        .into()
+        .group_sizes(students.len())
+        .and_then(|sizes| Layout::new(sizes, &[]))
+    else {
+        return vec![];
+    };
+    if students.is_empty() {
+        return vec![];
+    }
+
+    let options = SchedulingOptions::default();
+    let problem = Problem::new(students, layout, options.clone());
+    let clock = options.search.start();
+    // Keep every distinct grouping found; which of them are on the front is decided afterwards.
+    let top = TopAssignments::new(NUM_STARTING_POINTS, 1.0);
+    let assignments = climb_in_batches(&problem, &clock, NUM_STARTING_POINTS, top, |assignment| {
+        let mut rng = rng();
+        let seeding = *[Seeding::Random, Seeding::MostConstrained]
+            .choose(&mut rng)
+            .unwrap();
+        let arrangement = seeding.arrangement(&problem.layout, &problem.availability, &mut rng);
+        assignment.start_from(&problem, arrangement);
+        let aim = *[Aim::TotalScore, Aim::SharedHours, Aim::WorstGroup]
+            .choose(&mut rng)
+            .unwrap();
+        climb(assignment, &problem, &clock, aim);
+    });
+
+    // Assignments are best scoring first, so of groupings with the same objectives the best scoring is kept.
+    let objectives: Vec<Objectives> = assignments
+        .iter()
+        .map(|a| Objectives::new(a, &problem))
+        .collect();
+    let mut front: Vec<(usize, &Objectives)> = objectives
+        .iter()
+        .enumerate()
+        .filter(|&(i, o)| {
+            !objectives
+                .iter()
+                .enumerate()
+                .any(|(j, other)| other.dominates(o) || (j < i && other == o))
+        })
+        .collect();
+    front.sort_by_key(|(_, o)| Reverse(o.total_shared_hours));
+
+    front
+        .into_iter()
+        .map(|(i, o)| ParetoGrouping {
+            groups: assignments[i].groups(&problem),
+            total_shared_hours: o.total_shared_hours,
+            worst_group_score: o.worst_group_score,
+            inequality: o.inequality,
+        })
+        .collect()
+}
+
+/// What a climb swaps students to improve, to find different points of the front.
+#[derive(Debug, Clone, Copy)]
+enum Aim {
+    /// The usual score.
+    TotalScore,
+    /// Hours every student in a group is available, summed over groups, then the usual score.
+    SharedHours,
+    /// The worse score per student of the two groups in a swap, then the usual score. Never lowers the worst group.
+    WorstGroup,
+}
+
+/// Hill climbs towards `aim` until many swaps in a row don't improve it.
+fn climb(assignment: &mut Assignment, problem: &Problem, clock: &SearchClock, aim: Aim) {
+    let movable = problem.layout.movable();
+    if movable.len() < 2 {
+        return;
+    }
+
+    let mut rng = rng();
+    let mut tries_without_improvement = 0;
+    while tries_without_improvement < NUM_TRIES_FOR_BETTER_NEIGHBOR && !clock.should_stop() {
+        let a = *movable.choose(&mut rng).unwrap();
+        let b = *movable.choose(&mut rng).unwrap();
+        let improved = match aim {
+            Aim::TotalScore => assignment.try_swap(problem, a, b),
+            Aim::SharedHours => assignment.try_swap_if(
+                problem,
+                a,
+                b,
+                |group, score| (problem.shared_hours(group), score),
+                |old, new| {
+                    (new[0].0 + new[1].0, new[0].1 + new[1].1)
+                        > (old[0].0 + old[1].0, old[0].1 + old[1].1)
+                },
+            ),
+            Aim::WorstGroup => assignment.try_swap_if(
+                problem,
+                a,
+                b,
+                |group, score| (score_per_student(score, group.len()), score),
+                |old, new| {
+                    let worst = |v: &[(f64, isize); 2]| v[0].0.min(v[1].0);
+                    let total = |v: &[(f64, isize); 2]| v[0].1 + v[1].1;
+                    worst(&new) > worst(&old)
+                        || (worst(&new) == worst(&old) && total(&new) > total(&old))
+                },
+            ),
+        };
+
+        if improved {
+            tries_without_improvement = 0;
+        } else {
+            tries_without_improvement += 1;
+        }
+    }
+}
+
+pub(super) fn score_per_student(score: isize, group_size: usize) -> f64 {
+    score as f64 / group_size as f64
+}
+
+/// The objectives of `ParetoGrouping` for one assignment.
+#[derive(Debug, PartialEq)]
+struct Objectives {
+    total_shared_hours: usize,
+    worst_group_score: f64,
+    inequality: f64,
+}
+
+impl Objectives {
+    fn new(assignment: &Assignment, problem: &Problem) -> Self {
+        // Score per student and size of each group.
+        let groups: Vec<(f64, usize)> = assignment
+            .group_members(problem)
+            .zip(&assignment.group_scores)
+            .map(|(group, &score)| (score_per_student(score, group.len()), group.len()))
+            .collect();
+
+        Objectives {
+            total_shared_hours: assignment
+                .group_members(problem)
+                .map(|group| problem.shared_hours(group) as usize)
+                .sum(),
+            worst_group_score: groups
+                .iter()
+                .map(|&(score, _)| score)
+                .fold(f64::INFINITY, f64::min),
+            inequality: gini(&groups),
+        }
+    }
+
+    /// Whether this is at least as good as `other` on every objective and better on one.
+    fn dominates(&self, other: &Objectives) -> bool {
+        self.total_shared_hours >= other.total_shared_hours
+            && self.worst_group_score >= other.worst_group_score
+            && self.inequality <= other.inequality
+            && self != other
+    }
+}
+
+/// Gini coefficient of the score of each student's group, given the score per student and size of each group: the
+/// mean difference between two students' scores, relative to twice the mean score. Zero if the mean isn't positive.
+pub(super) fn gini(groups: &[(f64, usize)]) -> f64 {
+    let students: usize = groups.iter().map(|&(_, size)| size).sum();
+    let total: f64 = groups
+        .iter()
+        .map(|&(score, size)| score * size as f64)
+        .sum();
+    if students == 0 || total <= 0.0 {
+        return 0.0;
+    }
+
+    let differences: f64 = groups
+        .iter()
+        .flat_map(|&(a, size_a)| {
+            groups
+                .iter()
+                .map(move |&(b, size_b)| (a - b).abs() * (size_a * size_b) as f64)
+        })
+        .sum();
+    differences / (2.0 * students as f64 * total)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::random::random_students;
+
+    #[test]
+    fn gini_of_group_scores() {
+        assert_eq!(0.0, gini(&[(2.0, 3), (2.0, 4)]));
+        assert_eq!(0.5, gini(&[(0.0, 2), (4.0, 2)]));
+        // Bigger groups count for more of the students.
+        assert!(gini(&[(0.0, 1), (4.0, 3)]) < gini(&[(0.0, 3), (4.0, 1)]));
+        assert_eq!(0.0, gini(&[]));
+    }
+
+    #[test]
+    fn front_is_not_dominated() {
+        let (students, _) = random_students(30, Some(40));
+        let codes: Vec<String> = students.iter().map(|s| s.encode()).collect();
+
+        let front = create_pareto_groups(&codes, 5);
+        assert!(!front.is_empty());
+        for (i, grouping) in front.iter().enumerate() {
+            assert_eq!(6, grouping.groups.len());
+            let grouped: HashSet<&String> =
+                grouping.groups.iter().flat_map(|g| &g.students).collect();
+            assert_eq!(30, grouped.len());
+            assert!((0.0..=1.0).contains(&grouping.inequality));
+
+            for (j, other) in front.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                let at_least_as_good = other.total_shared_hours >= grouping.total_shared_hours
+                    && other.worst_group_score >= grouping.worst_group_score
+                    && other.inequality <= grouping.inequality;
+                assert!(!at_least_as_good);
+            }
+            if i > 0 {
+                assert!(front[i - 1].total_shared_hours >= grouping.total_shared_hours);
+            }
+        }
+    }
+
+    #[test]
+    fn no_front_without_groups() {
+        assert!(create_pareto_groups(&[], 5).is_empty());
+    }
+}
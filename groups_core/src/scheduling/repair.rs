@@ -0,0 +1,380 @@
+/// Repairs a grouping that has already been announced after students drop or join the class. Rather than
+/// rescheduling everyone, existing groups are kept as they are wherever possible: newcomers join the group they fit
+/// best, and groups left too small are either dissolved into other groups or topped up from the largest groups,
+/// whichever moves fewer students.
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::student::Student;
+
+use super::{
+    DisplayGroup, Group, display_groups, num_students_available_at_hour, suggested_meet_times,
+};
+
+/// A student who changed groups (or joined the class) during a repair.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StudentMove {
+    /// Encoded Student.
+    pub student: String,
+    /// Index of the student's group in the previous grouping, or None for a student who was just added.
+    pub from_group: Option<usize>,
+    /// Index of the student's group in `Repair::groups`.
+    pub to_group: usize,
+}
+
+/// The result of `repair_groups`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Repair {
+    /// The repaired groups, in the same order as the previous grouping. Groups that were dissolved are left out and
+    /// groups that had to be started for newcomers come last.
+    pub groups: Vec<Group>,
+    /// Every student placed in a different group than before, including all added students.
+    pub moves: Vec<StudentMove>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DisplayRepair {
+    groups: Vec<DisplayGroup>,
+    moves: Vec<StudentMove>,
+}
+
+#[wasm_bindgen]
+/// Same as `repair_groups`, but suitable for calling from WASM because it takes and returns JSValues.
+/// `previous_groups` is a Javascript array of arrays of encoded Student, one inner array per group.
+/// `added` and `removed` are Javascript arrays of encoded Student.
+/// `output_timezone` is the timezone which will be used when generating the `suggested_meet_times` array in
+/// each output group.
+/// Returns a JSON object with `groups` (as from `create_groups_wasm`) and `moves`, or null if the repair isn't possible.
+pub fn repair_groups_wasm(
+    previous_groups: JsValue,
+    added: JsValue,
+    removed: JsValue,
+    min_size: usize,
+    max_size: usize,
+    output_timezone: String,
+) -> JsValue {
+    let previous: Vec<Vec<String>> = serde_wasm_bindgen::from_value(previous_groups).unwrap();
+    let added: Vec<String> = serde_wasm_bindgen::from_value(added).unwrap();
+    let removed: Vec<String> = serde_wasm_bindgen::from_value(removed).unwrap();
+
+    let display = repair_groups(&previous, &added, &removed, min_size, max_size).map(|repair| {
+        DisplayRepair {
+            groups: display_groups(&repair.groups, &output_timezone),
+            moves: repair.moves,
+        }
+    });
+    serde_wasm_bindgen::to_value(&display).unwrap()
+}
+
+/// Updates `previous_groups` for a changed class roster, keeping existing groups together as much as possible.
+/// Students in `removed` are taken out of their group, and students in `added` join the group with room (fewer than
+/// `max_size` members) that they share the most available hours with, or a new group if every group is full.
+/// Groups left with fewer than `min_size` members are then either dissolved into other groups or topped up with the
+/// best fitting members of the largest groups, whichever moves fewer students.
+/// Returns None if a removed student isn't in `previous_groups` or the size bounds are invalid.
+pub fn repair_groups(
+    previous_groups: &[Vec<String>],
+    added: &[String],
+    removed: &[String],
+    min_size: usize,
+    max_size: usize,
+) -> Option<Repair> {
+    if max_size == 0 || min_size > max_size {
+        return None;
+    }
+
+    let mut codes = vec![];
+    let mut students = vec![];
+    let mut groups: Vec<Vec<usize>> = vec![];
+    for previous in previous_groups {
+        let mut group = vec![];
+        for code in previous {
+            if let Some(student) = Student::from_encoded(code) {
+                group.push(students.len());
+                codes.push(code.clone());
+                students.push(student);
+            }
+        }
+        groups.push(group);
+    }
+
+    let mut original_group = vec![None; students.len()];
+    for (g, group) in groups.iter().enumerate() {
+        for &s in group {
+            original_group[s] = Some(g);
+        }
+    }
+
+    for code in removed {
+        let (g, position) = groups.iter().enumerate().find_map(|(g, group)| {
+            group
+                .iter()
+                .position(|&s| &codes[s] == code)
+                .map(|position| (g, position))
+        })?;
+        groups[g].remove(position);
+    }
+
+    for code in added {
+        let Some(student) = Student::from_encoded(code) else {
+            continue;
+        };
+        let s = students.len();
+        codes.push(code.clone());
+        students.push(student);
+        original_group.push(None);
+
+        match best_group_with_room(&groups, s, max_size, None, &students) {
+            Some(g) => groups[g].push(s),
+            None => groups.push(vec![s]),
+        }
+    }
+
+    fix_undersized_groups(&mut groups, min_size, max_size, &students);
+
+    // Compact away empty groups, remembering where each working group ended up.
+    let mut output_index = vec![None; groups.len()];
+    let mut result = vec![];
+    for (g, group) in groups.iter().enumerate() {
+        if group.is_empty() {
+            continue;
+        }
+        output_index[g] = Some(result.len());
+
+        let mut encoded_students: Vec<String> = group.iter().map(|&s| codes[s].clone()).collect();
+        encoded_students.sort_unstable(); // To make unit testing easier.
+        result.push(Group {
+            students: encoded_students,
+            suggested_meet_times: suggested_meet_times(group, &students),
+        });
+    }
+
+    let mut moves = vec![];
+    for (g, group) in groups.iter().enumerate() {
+        for &s in group {
+            if original_group[s] != Some(g) {
+                moves.push(StudentMove {
+                    student: codes[s].clone(),
+                    from_group: original_group[s],
+                    to_group: output_index[g].unwrap(),
+                });
+            }
+        }
+    }
+    moves.sort_unstable_by(|a, b| a.student.cmp(&b.student)); // To make unit testing easier.
+
+    Some(Repair {
+        groups: result,
+        moves,
+    })
+}
+
+/// Dissolves or tops up every non-empty group with fewer than `min_size` members, smallest first.
+fn fix_undersized_groups(
+    groups: &mut [Vec<usize>],
+    min_size: usize,
+    max_size: usize,
+    students: &[Student],
+) {
+    loop {
+        let Some(small) = (0..groups.len())
+            .filter(|&g| !groups[g].is_empty() && groups[g].len() < min_size)
+            .min_by_key(|&g| groups[g].len())
+        else {
+            return;
+        };
+
+        let spare_room: usize = (0..groups.len())
+            .filter(|&g| g != small && !groups[g].is_empty())
+            .map(|g| max_size.saturating_sub(groups[g].len()))
+            .sum();
+        let spare_members: usize = (0..groups.len())
+            .filter(|&g| g != small)
+            .map(|g| groups[g].len().saturating_sub(min_size))
+            .sum();
+
+        let dissolve_moves = groups[small].len();
+        let top_up_moves = min_size - groups[small].len();
+        let can_dissolve = spare_room >= dissolve_moves;
+        let can_top_up = spare_members >= top_up_moves;
+
+        if can_dissolve && (!can_top_up || dissolve_moves <= top_up_moves) {
+            for s in std::mem::take(&mut groups[small]) {
+                let g = best_group_with_room(groups, s, max_size, Some(small), students)
+                    .expect("spare room was counted before dissolving");
+                groups[g].push(s);
+            }
+        } else if can_top_up {
+            for _ in 0..top_up_moves {
+                // Take from the largest group that can spare someone, picking whoever fits in best.
+                let donor = (0..groups.len())
+                    .filter(|&g| g != small && groups[g].len() > min_size)
+                    .max_by_key(|&g| groups[g].len())
+                    .expect("spare members were counted before topping up");
+                let position = (0..groups[donor].len())
+                    .max_by_key(|&p| fit(&groups[small], groups[donor][p], students))
+                    .unwrap();
+                let s = groups[donor].remove(position);
+                groups[small].push(s);
+            }
+        } else {
+            // Too few students overall to bring this group up to size. Leave it as is.
+            return;
+        }
+    }
+}
+
+/// The non-empty group with fewer than `max_size` members that `student` fits best with, other than `exclude`.
+/// Ties go to the smaller group.
+fn best_group_with_room(
+    groups: &[Vec<usize>],
+    student: usize,
+    max_size: usize,
+    exclude: Option<usize>,
+    students: &[Student],
+) -> Option<usize> {
+    (0..groups.len())
+        .filter(|&g| Some(g) != exclude && !groups[g].is_empty() && groups[g].len() < max_size)
+        .max_by_key(|&g| {
+            (
+                fit(&groups[g], student, students),
+                std::cmp::Reverse(groups[g].len()),
+                std::cmp::Reverse(g),
+            )
+        })
+}
+
+/// How well `candidate` fits with `members`: the number of hours they are all available, then the most of them
+/// that are available at the same time.
+fn fit(members: &[usize], candidate: usize, students: &[Student]) -> (usize, u32) {
+    let mut team = members.to_vec();
+    team.push(candidate);
+
+    let available = num_students_available_at_hour(&team, students);
+    let common_hours = available
+        .iter()
+        .filter(|&&count| count as usize == team.len())
+        .count();
+    (common_hours, *available.iter().max().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Codes for students Test1 to Test8. Test1 and Test2 share availability, as do Test3 and Test4, and so on.
+    fn codes() -> Vec<String> {
+        [
+            "VGVzdDF8QWZyaWNhL0FiaWRqYW58MTkyMHwwfDB8MHwwfDA=",
+            "VGVzdDJ8QWZyaWNhL0FiaWRqYW58MTkyMHwwfDB8MHwwfDA=",
+            "VGVzdDN8QWZyaWNhL0FiaWRqYW58MzA3MjB8MHwwfDB8MHww",
+            "VGVzdDR8QWZyaWNhL0FiaWRqYW58MzA3MjB8MHwwfDB8MHww",
+            "VGVzdDV8QWZyaWNhL0FiaWRqYW58NDkxNTIwfDB8MHwwfDB8MA==",
+            "VGVzdDZ8QWZyaWNhL0FiaWRqYW58NDkxNTIwfDB8MHwwfDB8MA==",
+            "VGVzdDd8QWZyaWNhL0FiaWRqYW58Nzg2NDMyMHwwfDB8MHwwfDA=",
+            "VGVzdDh8QWZyaWNhL0FiaWRqYW58Nzg2NDMyMHwwfDB8MHwwfDA=",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect()
+    }
+
+    fn members(repair: &Repair) -> Vec<Vec<String>> {
+        repair.groups.iter().map(|g| g.students.clone()).collect()
+    }
+
+    #[test]
+    fn newcomer_joins_best_fitting_group() {
+        let c = codes();
+        let previous = vec![vec![c[0].clone()], vec![c[2].clone()]];
+
+        let repair = repair_groups(&previous, &[c[3].clone()], &[], 1, 2).unwrap();
+        assert_eq!(
+            members(&repair),
+            vec![vec![c[0].clone()], vec![c[2].clone(), c[3].clone()]]
+        );
+        assert_eq!(
+            repair.moves,
+            vec![StudentMove {
+                student: c[3].clone(),
+                from_group: None,
+                to_group: 1
+            }]
+        );
+        assert_eq!(repair.groups[1].suggested_meet_times, vec![11, 12, 13, 14]);
+    }
+
+    #[test]
+    fn newcomer_starts_group_when_all_full() {
+        let c = codes();
+        let previous = vec![vec![c[0].clone(), c[1].clone()]];
+
+        let repair = repair_groups(&previous, &[c[2].clone()], &[], 1, 2).unwrap();
+        assert_eq!(
+            members(&repair),
+            vec![vec![c[0].clone(), c[1].clone()], vec![c[2].clone()]]
+        );
+    }
+
+    #[test]
+    fn undersized_group_topped_up_from_largest() {
+        let c = codes();
+        let previous = vec![
+            vec![c[0].clone(), c[1].clone(), c[2].clone()],
+            vec![c[3].clone(), c[4].clone()],
+        ];
+
+        // Test4 is left alone and every group is full, so the best fitting member of the first group joins them.
+        let repair = repair_groups(&previous, &[], &[c[4].clone()], 2, 3).unwrap();
+        assert_eq!(
+            members(&repair),
+            vec![
+                vec![c[0].clone(), c[1].clone()],
+                vec![c[2].clone(), c[3].clone()]
+            ]
+        );
+        assert_eq!(
+            repair.moves,
+            vec![StudentMove {
+                student: c[2].clone(),
+                from_group: Some(0),
+                to_group: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn undersized_group_dissolved() {
+        let c = codes();
+        let previous = vec![
+            vec![c[0].clone(), c[2].clone()],
+            vec![c[1].clone(), c[4].clone()],
+            vec![c[5].clone(), c[6].clone()],
+        ];
+
+        // Test6 is left alone. Nobody can be spared, so they join Test5's group and their old group goes away.
+        let repair = repair_groups(&previous, &[], &[c[6].clone()], 2, 3).unwrap();
+        assert_eq!(
+            members(&repair),
+            vec![
+                vec![c[0].clone(), c[2].clone()],
+                vec![c[1].clone(), c[4].clone(), c[5].clone()]
+            ]
+        );
+        assert_eq!(
+            repair.moves,
+            vec![StudentMove {
+                student: c[5].clone(),
+                from_group: Some(2),
+                to_group: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn unknown_removal() {
+        let c = codes();
+        let previous = vec![vec![c[0].clone(), c[1].clone()]];
+        assert_eq!(None, repair_groups(&previous, &[], &[c[2].clone()], 1, 2));
+    }
+}
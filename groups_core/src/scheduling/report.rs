@@ -0,0 +1,320 @@
+/// A closer look at how well each group of a grouping can meet than `DisplayGroup` gives: how many hours everyone
+/// shares, how well each member is covered by the suggested times, and how far apart their timezones are, plus
+/// statistics over the whole grouping.
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use super::availability::WeekBits;
+use super::pareto::gini;
+use super::{DisplayGroup, Group, display_groups, suggested_meet_times};
+use crate::constants::NUM_HOURS_PER_DAY;
+use crate::student::Student;
+
+/// Number of equal-width buckets `GroupingStats::histogram` splits the fractions of each group who can meet into.
+const NUM_HISTOGRAM_BUCKETS: usize = 10;
+
+/// How well one member of a group is covered by the group's suggested meet times.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemberCoverage {
+    /// Encoded Student.
+    pub student: String,
+    /// Fraction of the group's suggested meet times the student is available at.
+    pub coverage: f64,
+}
+
+/// Report on one group, from `report_groups`.
+#[derive(Debug, PartialEq)]
+pub struct GroupReport {
+    /// Number of hours in the week when every member is available.
+    pub common_hours: usize,
+    /// Most consecutive hours when every member is available.
+    pub longest_common_block: usize,
+    /// In the same order as the group's students. Students whose codes don't decode are left out.
+    pub member_coverage: Vec<MemberCoverage>,
+    /// Encoded Students who aren't available at any of the suggested meet times.
+    pub cannot_attend: Vec<String>,
+    /// Hours around the clock spanned by the members' UTC offsets, going the short way round: UTC+12 and UTC-11 are an
+    /// hour apart, not 23. Offsets are taken at the time of the report, so daylight saving time can change the spread.
+    pub timezone_spread: usize,
+    /// Fraction of the group available at the best of its suggested meet times.
+    pub percent_at_suggested_times: f64,
+}
+
+/// Statistics over every group of a grouping of the fraction of the group available at the best of its suggested
+/// meet times (`GroupReport::percent_at_suggested_times`).
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct GroupingStats {
+    pub min: f64,
+    pub median: f64,
+    pub max: f64,
+    /// Gini coefficient, weighting each group by its size. 0 when every group is as well off.
+    pub inequality: f64,
+    /// Number of groups in each tenth from 0 up to 1, with groups where everyone can meet in the last.
+    pub histogram: Vec<usize>,
+}
+
+/// The result of `report_groups`.
+#[derive(Debug, PartialEq)]
+pub struct GroupingReport {
+    /// In the same order as the groups reported on.
+    pub groups: Vec<GroupReport>,
+    pub stats: GroupingStats,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DisplayGroupReport {
+    #[serde(flatten)]
+    group: DisplayGroup,
+    common_hours: usize,
+    longest_common_block: usize,
+    member_coverage: Vec<MemberCoverage>,
+    cannot_attend: Vec<String>,
+    timezone_spread: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DisplayGroupingReport {
+    groups: Vec<DisplayGroupReport>,
+    stats: GroupingStats,
+}
+
+#[wasm_bindgen]
+/// Same as `report_groups`, but suitable for calling from WASM because it takes and returns JSValues.
+/// `groups` is a Javascript array of arrays of encoded Student, one inner array per group. Each group's suggested meet
+/// times are worked out as they are for generated groups.
+/// `output_timezone` is the timezone which will be used when generating the `suggested_meet_times` array in
+/// each output group.
+/// Returns a JSON object with `groups`, an array of groups as from `create_groups_wasm` which also have
+/// `common_hours`, `longest_common_block`, `member_coverage` (an array of `{ student, coverage }`), `cannot_attend` and
+/// `timezone_spread`, and `stats`, an object with `min`, `median`, `max`, `inequality` and `histogram`.
+pub fn report_groups_wasm(groups: JsValue, output_timezone: String) -> JsValue {
+    let groups: Vec<Vec<String>> = serde_wasm_bindgen::from_value(groups).unwrap();
+    let groups: Vec<Group> = groups
+        .into_iter()
+        .map(|codes| {
+            let students: Vec<Student> = codes
+                .iter()
+                .filter_map(|c| Student::from_encoded(c))
+                .collect();
+            let members: Vec<usize> = (0..students.len()).collect();
+            Group {
+                suggested_meet_times: suggested_meet_times(&members, &students),
+                students: codes,
+            }
+        })
+        .collect();
+
+    let report = report_groups(&groups);
+    let display = DisplayGroupingReport {
+        groups: display_groups(&groups, &output_timezone)
+            .into_iter()
+            .zip(report.groups)
+            .map(|(group, r)| DisplayGroupReport {
+                group,
+                common_hours: r.common_hours,
+                longest_common_block: r.longest_common_block,
+                member_coverage: r.member_coverage,
+                cannot_attend: r.cannot_attend,
+                timezone_spread: r.timezone_spread,
+            })
+            .collect(),
+        stats: report.stats,
+    };
+    serde_wasm_bindgen::to_value(&display).unwrap()
+}
+
+/// Reports on how well each of `groups` can meet at its suggested meet times, and on the grouping as a whole.
+pub fn report_groups(groups: &[Group]) -> GroupingReport {
+    let groups: Vec<GroupReport> = groups.iter().map(report_group).collect();
+
+    let mut percents: Vec<f64> = groups
+        .iter()
+        .map(|g| g.percent_at_suggested_times)
+        .collect();
+    percents.sort_by(f64::total_cmp);
+    let median = match percents.len() {
+        0 => 0.0,
+        n if n % 2 == 1 => percents[n / 2],
+        n => (percents[n / 2 - 1] + percents[n / 2]) / 2.0,
+    };
+    let mut histogram = vec![0; NUM_HISTOGRAM_BUCKETS];
+    for percent in &percents {
+        let bucket = (percent * NUM_HISTOGRAM_BUCKETS as f64) as usize;
+        histogram[bucket.min(NUM_HISTOGRAM_BUCKETS - 1)] += 1;
+    }
+    let sizes_and_percents: Vec<(f64, usize)> = groups
+        .iter()
+        .map(|g| (g.percent_at_suggested_times, g.member_coverage.len()))
+        .collect();
+
+    GroupingReport {
+        stats: GroupingStats {
+            min: percents.first().copied().unwrap_or(0.0),
+            median,
+            max: percents.last().copied().unwrap_or(0.0),
+            inequality: gini(&sizes_and_percents),
+            histogram,
+        },
+        groups,
+    }
+}
+
+fn report_group(group: &Group) -> GroupReport {
+    let members: Vec<(&String, Student)> = group
+        .students
+        .iter()
+        .filter_map(|code| Some((code, Student::from_encoded(code)?)))
+        .collect();
+    let availability: Vec<WeekBits> = members
+        .iter()
+        .map(|(_, s)| WeekBits::from_student(s))
+        .collect();
+    let suggested = WeekBits::from_hours(group.suggested_meet_times.iter().copied());
+    let num_suggested = suggested.count_ones();
+
+    let common = if availability.is_empty() {
+        WeekBits::default()
+    } else {
+        availability
+            .iter()
+            .fold(WeekBits::all(), |common, &hours| common & hours)
+    };
+    let member_coverage: Vec<MemberCoverage> = members
+        .iter()
+        .zip(&availability)
+        .map(|(&(code, _), &hours)| MemberCoverage {
+            student: code.clone(),
+            coverage: if num_suggested == 0 {
+                0.0
+            } else {
+                (hours & suggested).count_ones() as f64 / num_suggested as f64
+            },
+        })
+        .collect();
+    let timezone_spread = timezone_spread(members.iter().map(|(_, s)| s.utc_offset_hours()));
+
+    GroupReport {
+        common_hours: common.count_ones() as usize,
+        longest_common_block: common.longest_run(),
+        cannot_attend: member_coverage
+            .iter()
+            .filter(|m| m.coverage == 0.0)
+            .map(|m| m.student.clone())
+            .collect(),
+        member_coverage,
+        timezone_spread,
+        percent_at_suggested_times: group.percent_at_suggested_times(),
+    }
+}
+
+/// Fewest hours around the 24 hour clock that cover every one of `offsets`: the whole day less the widest gap between
+/// neighboring offsets. Zero if there are none.
+fn timezone_spread(offsets: impl Iterator<Item = i8>) -> usize {
+    let hours: Vec<usize> = offsets
+        .map(|offset| offset.rem_euclid(NUM_HOURS_PER_DAY as i8) as usize)
+        .sorted_unstable()
+        .dedup()
+        .collect();
+    let (Some(&first), Some(&last)) = (hours.first(), hours.last()) else {
+        return 0;
+    };
+    let widest_gap = hours
+        .windows(2)
+        .map(|pair| pair[1] - pair[0])
+        .chain([first + NUM_HOURS_PER_DAY - last])
+        .max()
+        .unwrap_or(NUM_HOURS_PER_DAY);
+    NUM_HOURS_PER_DAY - widest_gap
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::student_with_hours;
+
+    #[test]
+    fn reports_each_group() {
+        let a = student_with_hours("a", "UTC", &[10, 11, 12, 30]).encode();
+        let b = student_with_hours("b", "UTC", &[10, 11, 12, 13]).encode();
+        let c = student_with_hours("c", "Asia/Tokyo", &[100]).encode();
+        let group = Group {
+            students: vec![a.clone(), b.clone(), c.clone()],
+            suggested_meet_times: vec![10, 11, 12],
+        };
+
+        let report = report_group(&group);
+        assert_eq!(0, report.common_hours);
+        assert_eq!(0, report.longest_common_block);
+        assert_eq!(
+            vec![1.0, 1.0, 0.0],
+            report
+                .member_coverage
+                .iter()
+                .map(|m| m.coverage)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(vec![c], report.cannot_attend);
+        assert_eq!(9, report.timezone_spread);
+        assert_eq!(2.0 / 3.0, report.percent_at_suggested_times);
+
+        let pair = report_group(&Group {
+            students: vec![a, b],
+            suggested_meet_times: vec![10, 11, 12],
+        });
+        assert_eq!(3, pair.common_hours);
+        assert_eq!(3, pair.longest_common_block);
+        assert!(pair.cannot_attend.is_empty());
+        assert_eq!(0, pair.timezone_spread);
+    }
+
+    #[test]
+    fn spread_goes_the_short_way_round() {
+        assert_eq!(0, timezone_spread([].into_iter()));
+        assert_eq!(0, timezone_spread([5, 5].into_iter()));
+        assert_eq!(1, timezone_spread([12, -11].into_iter()));
+        assert_eq!(9, timezone_spread([0, 9].into_iter()));
+        // UTC+14 and UTC-10 keep the same clock, a day apart.
+        assert_eq!(0, timezone_spread([14, -10].into_iter()));
+        // The widest gap, from +8 round to -3, is left out.
+        assert_eq!(11, timezone_spread([-3, 0, 8].into_iter()));
+
+        let group = Group {
+            students: vec![
+                student_with_hours("a", "Etc/GMT-12", &[10]).encode(),
+                student_with_hours("b", "Etc/GMT+11", &[10]).encode(),
+            ],
+            suggested_meet_times: vec![],
+        };
+        assert_eq!(1, report_group(&group).timezone_spread);
+    }
+
+    #[test]
+    fn grouping_stats() {
+        let a = student_with_hours("a", "UTC", &[10]).encode();
+        let b = student_with_hours("b", "UTC", &[10]).encode();
+        let c = student_with_hours("c", "UTC", &[20]).encode();
+        let d = student_with_hours("d", "UTC", &[30]).encode();
+        let group = |students: Vec<String>| Group {
+            students,
+            suggested_meet_times: vec![10],
+        };
+
+        let report = report_groups(&[
+            group(vec![a.clone(), b.clone()]),
+            group(vec![a.clone(), c.clone()]),
+            group(vec![c, d]),
+        ]);
+        let stats = &report.stats;
+        assert_eq!((0.0, 0.5, 1.0), (stats.min, stats.median, stats.max));
+        assert_eq!(vec![1, 0, 0, 0, 0, 1, 0, 0, 0, 1], stats.histogram);
+        assert!(stats.inequality > 0.0);
+
+        let even = report_groups(&[group(vec![a.clone(), b.clone()]), group(vec![a, b])]);
+        assert_eq!(1.0, even.stats.median);
+        assert_eq!(0.0, even.stats.inequality);
+
+        let empty = report_groups(&[]);
+        assert_eq!(vec![0; NUM_HISTOGRAM_BUCKETS], empty.stats.histogram);
+    }
+}
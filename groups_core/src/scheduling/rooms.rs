@@ -0,0 +1,370 @@
+/// Books each group a room to meet in, for courses where teams meet in one of a few labs. Rooms are only open some
+/// hours of the week and hold a limited number of groups at once, so a group is booked a block of consecutive hours
+/// when its whole group is free and one of the rooms is open and not already full. Rooms can also be part of
+/// `SchedulingOptions`, so groups are formed that can meet while a room is open.
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use super::availability::WeekBits;
+use super::{
+    DefaultStrategy, DisplayGroup, Group, GroupSizing, SchedulingOptions, SchedulingStrategy,
+};
+use super::{create_groups_with_options, display_groups, pretty_hours};
+use crate::constants::NUM_HOURS_PER_WEEK;
+
+/// Score taken off a group that can't meet while any room is open, as a fraction of a perfect group's score.
+pub(crate) const ROOMLESS_PENALTY: f64 = 0.5;
+
+/// A room groups can meet in.
+/// In Javascript this is written as `{ "name": "Lab 1", "open_hours": [9, 10, 11], "capacity": 4 }`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Room {
+    pub name: String,
+    /// Hours in the week the room is open (in UTC). 0 = Monday at 12 AM, 1 = Monday at 1 AM, etc.
+    pub open_hours: Vec<usize>,
+    /// Most groups that can meet in the room at once.
+    pub capacity: usize,
+}
+
+impl Room {
+    /// Hours (in UTC) a block of `hours` consecutive hours could start at while the room is open, none if it holds no
+    /// groups. Blocks don't wrap around the end of the week.
+    fn open_starts(&self, hours: usize) -> WeekBits {
+        if self.capacity == 0 || hours == 0 || hours > NUM_HOURS_PER_WEEK {
+            return WeekBits::default();
+        }
+        let open = WeekBits::from_hours(
+            self.open_hours
+                .iter()
+                .copied()
+                .filter(|&h| h < NUM_HOURS_PER_WEEK),
+        );
+        WeekBits::from_hours(
+            open.run_starts(hours)
+                .iter_ones()
+                .filter(|&start| start + hours <= NUM_HOURS_PER_WEEK),
+        )
+    }
+}
+
+/// Rooms groups should be able to meet in, for `SchedulingOptions::rooms`.
+/// In Javascript this is written as `{ "rooms": [<Room>, ...], "hours": 2 }`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomRequirement {
+    pub rooms: Vec<Room>,
+    /// Consecutive hours each group meets for.
+    pub hours: usize,
+}
+
+impl RoomRequirement {
+    /// Hours (in UTC) a meeting could start at in one of the rooms, ignoring how full they get.
+    pub(crate) fn open_starts(&self) -> WeekBits {
+        self.rooms.iter().fold(WeekBits::default(), |starts, room| {
+            starts | room.open_starts(self.hours)
+        })
+    }
+
+    /// Hours of every meeting that could start at one of `starts`.
+    pub(crate) fn blocks(&self, starts: WeekBits) -> WeekBits {
+        WeekBits::from_hours(
+            starts
+                .iter_ones()
+                .flat_map(|start| start..start + self.hours),
+        )
+    }
+}
+
+/// When and where a group meets, from `book_rooms`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoomBooking {
+    /// Name of the room.
+    pub room: String,
+    /// Hour of the week the booking starts at (in UTC). 0 = Monday at 12 AM, 1 = Monday at 1 AM, etc.
+    pub start: usize,
+    /// Number of consecutive hours booked.
+    pub hours: usize,
+}
+
+/// Groups returned by `create_groups_with_rooms`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GroupsWithRooms {
+    pub groups: Vec<Group>,
+    /// The booking of each of `groups`, in the same order, or None if no room could fit the group in.
+    pub bookings: Vec<Option<RoomBooking>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DisplayGroupWithRoom {
+    #[serde(flatten)]
+    group: DisplayGroup,
+    #[serde(flatten)]
+    room: DisplayRoom,
+}
+
+/// A group's `RoomBooking` as passed to Javascript.
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct DisplayRoom {
+    room: Option<String>,
+    room_start: Option<String>,
+}
+
+impl DisplayRoom {
+    pub(crate) fn new(booking: Option<RoomBooking>, timezone: &str) -> Self {
+        DisplayRoom {
+            room_start: booking
+                .as_ref()
+                .map(|b| pretty_hours(&[b.start], timezone).remove(0)),
+            room: booking.map(|b| b.room),
+        }
+    }
+}
+
+#[wasm_bindgen]
+/// Same as `create_groups_with_rooms`, but suitable for calling from WASM because it takes and returns JSValues.
+/// `students` is a Javascript array of encoded Student (strings).
+/// `sizing` is a `GroupSizing` object, as in `create_groups_wasm`.
+/// `rooms` is a Javascript array of `Room` objects: `{ "name": "Lab 1", "open_hours": [9, 10, 11], "capacity": 4 }`,
+/// with `open_hours` in UTC.
+/// `output_timezone` is the timezone which will be used when generating the `suggested_meet_times` array and
+/// `room_start` in each output group.
+/// Returns a Javascript array of groups as from `create_groups_wasm` which also have `room` and `room_start`, both
+/// null for groups no room could fit in.
+pub fn create_groups_with_rooms_wasm(
+    students: JsValue,
+    sizing: JsValue,
+    rooms: JsValue,
+    hours: usize,
+    output_timezone: String,
+) -> JsValue {
+    let student_strings: Vec<String> = serde_wasm_bindgen::from_value(students).unwrap();
+    let sizing: GroupSizing = serde_wasm_bindgen::from_value(sizing).unwrap();
+    let rooms: Vec<Room> = serde_wasm_bindgen::from_value(rooms).unwrap();
+
+    let result =
+        create_groups_with_rooms::<DefaultStrategy>(&student_strings, sizing, &rooms, hours);
+    let display: Vec<DisplayGroupWithRoom> = display_groups(&result.groups, &output_timezone)
+        .into_iter()
+        .zip(result.bookings)
+        .map(|(group, booking)| DisplayGroupWithRoom {
+            group,
+            room: DisplayRoom::new(booking, &output_timezone),
+        })
+        .collect();
+    serde_wasm_bindgen::to_value(&display).unwrap()
+}
+
+/// Like `create_groups`, but looks for groups that can meet for `hours` consecutive hours while one of `rooms` is
+/// open, then books each group a room with `book_rooms`. To combine rooms with other options, set
+/// `SchedulingOptions::rooms` and call `book_rooms` on the groups from `create_groups_with_options`.
+pub fn create_groups_with_rooms<S: SchedulingStrategy>(
+    students_encoded: &[String],
+    sizing: impl Into<GroupSizing>,
+    rooms: &[Room],
+    hours: usize,
+) -> GroupsWithRooms {
+    let groups = create_groups_with_options::<S>(
+        students_encoded,
+        sizing,
+        SchedulingOptions {
+            rooms: Some(RoomRequirement {
+                rooms: rooms.to_vec(),
+                hours,
+            }),
+            ..SchedulingOptions::default()
+        },
+    );
+    GroupsWithRooms {
+        bookings: book_rooms(&groups, rooms, hours),
+        groups,
+    }
+}
+
+/// Books each of `groups` `hours` consecutive hours in one of `rooms` while it's open, never booking more groups into
+/// a room at once than its capacity. Bookings are made when every member of the group is available (or, for groups
+/// whose members never all are, within its suggested meet times) and don't wrap around the end of the week. Returns
+/// the booking of each group, in the same order, or None for groups that couldn't be fit in.
+///
+/// Booking is greedy: groups with the fewest rooms and times to choose from are booked first, each into the room and
+/// time the other groups want least. That usually books every group that can be, but isn't guaranteed to book as many
+/// groups as possible.
+pub fn book_rooms(groups: &[Group], rooms: &[Room], hours: usize) -> Vec<Option<RoomBooking>> {
+    if hours == 0 || hours > NUM_HOURS_PER_WEEK {
+        return vec![None; groups.len()];
+    }
+
+    let open_starts: Vec<WeekBits> = rooms.iter().map(|room| room.open_starts(hours)).collect();
+    // The rooms and start hours each group could be booked at.
+    let options: Vec<Vec<(usize, usize)>> = groups
+        .iter()
+        .map(|group| {
+            let starts = group.meeting_hours().run_starts(hours);
+            open_starts
+                .iter()
+                .enumerate()
+                .flat_map(|(r, &open)| {
+                    (starts & open)
+                        .iter_ones()
+                        .map(move |start| (r, start))
+                        .collect::<Vec<_>>()
+                })
+                .collect()
+        })
+        .collect();
+
+    // How many groups could use each hour of each room.
+    let mut demand = vec![vec![0; NUM_HOURS_PER_WEEK]; rooms.len()];
+    for &(r, start) in options.iter().flatten() {
+        for n in &mut demand[r][start..start + hours] {
+            *n += 1;
+        }
+    }
+    let demand_for =
+        |(r, start): (usize, usize)| -> usize { demand[r][start..start + hours].iter().sum() };
+
+    let mut order: Vec<usize> = (0..groups.len()).collect();
+    order.sort_by_key(|&g| options[g].len());
+
+    let mut booked = vec![vec![0; NUM_HOURS_PER_WEEK]; rooms.len()];
+    let mut bookings = vec![None; groups.len()];
+    for g in order {
+        let best = options[g]
+            .iter()
+            .copied()
+            .filter(|&(r, start)| {
+                booked[r][start..start + hours]
+                    .iter()
+                    .all(|&n| n < rooms[r].capacity)
+            })
+            .min_by_key(|&option| demand_for(option));
+        if let Some((r, start)) = best {
+            for n in &mut booked[r][start..start + hours] {
+                *n += 1;
+            }
+            bookings[g] = Some(RoomBooking {
+                room: rooms[r].name.clone(),
+                start,
+                hours,
+            });
+        }
+    }
+    bookings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::student_with_hours;
+
+    fn group(suggested_meet_times: Vec<usize>) -> Group {
+        Group {
+            students: vec![],
+            suggested_meet_times,
+        }
+    }
+
+    fn room(name: &str, open_hours: Vec<usize>, capacity: usize) -> Room {
+        Room {
+            name: name.to_string(),
+            open_hours,
+            capacity,
+        }
+    }
+
+    #[test]
+    fn books_within_capacity() {
+        // Lab 1 is open Monday at 9 and 10 AM, Lab 2 only at 10 AM.
+        let rooms = [room("Lab 1", vec![9, 10], 1), room("Lab 2", vec![10], 1)];
+        let groups = [group(vec![9, 10]), group(vec![10]), group(vec![10])];
+
+        // The first group is booked at 9 AM, leaving both labs free at 10 for the others.
+        let bookings = book_rooms(&groups, &rooms, 1);
+        let booked: Vec<Option<(&str, usize)>> = bookings
+            .iter()
+            .map(|b| b.as_ref().map(|b| (b.room.as_str(), b.start)))
+            .collect();
+        assert_eq!(
+            vec![Some(("Lab 1", 9)), Some(("Lab 1", 10)), Some(("Lab 2", 10))],
+            booked
+        );
+
+        // Two hours only fit in Lab 1, once.
+        let bookings = book_rooms(&[group(vec![9, 10]), group(vec![9, 10])], &rooms, 2);
+        assert_eq!(
+            vec![
+                Some(RoomBooking {
+                    room: "Lab 1".to_string(),
+                    start: 9,
+                    hours: 2,
+                }),
+                None,
+            ],
+            bookings
+        );
+
+        // A room with space for two takes two groups at once, but not three.
+        let big = [room("Hall", vec![10], 2)];
+        let bookings = book_rooms(
+            &[group(vec![10]), group(vec![10]), group(vec![10])],
+            &big,
+            1,
+        );
+        assert_eq!(2, bookings.iter().flatten().count());
+    }
+
+    #[test]
+    fn books_hours_the_whole_group_shares() {
+        let group = Group {
+            students: vec![
+                student_with_hours("a", "UTC", &[9, 10, 11]).encode(),
+                student_with_hours("b", "UTC", &[9, 10, 11, 12]).encode(),
+            ],
+            suggested_meet_times: vec![9],
+        };
+        let bookings = book_rooms(&[group], &[room("Lab", vec![11, 12], 1)], 1);
+        assert_eq!(Some(11), bookings[0].as_ref().map(|b| b.start));
+    }
+
+    #[test]
+    fn groups_formed_around_rooms() {
+        // Everyone shares 1 to 5 AM, when the lab is closed. Only a and b also share 8 to noon, and only c and d
+        // share 4 to 8 PM, when it's open.
+        let early = [1, 2, 3, 4];
+        let morning = [8, 9, 10, 11];
+        let evening = [16, 17, 18, 19];
+        let codes = vec![
+            student_with_hours("a", "UTC", &[early, morning].concat()).encode(),
+            student_with_hours("b", "UTC", &[early, morning].concat()).encode(),
+            student_with_hours("c", "UTC", &[early, evening].concat()).encode(),
+            student_with_hours("d", "UTC", &[early, evening].concat()).encode(),
+        ];
+        let lab = room("Lab", [morning, evening].concat(), 1);
+
+        let result = create_groups_with_rooms::<DefaultStrategy>(&codes, 2, &[lab], 2);
+        let mut groups: Vec<Vec<String>> =
+            result.groups.iter().map(|g| g.students.clone()).collect();
+        groups.iter_mut().for_each(|g| g.sort_unstable());
+        groups.sort_unstable();
+        let mut expected = vec![codes[..2].to_vec(), codes[2..].to_vec()];
+        expected.sort_unstable();
+        assert_eq!(expected, groups);
+        assert!(result.bookings.iter().all(Option::is_some));
+    }
+
+    #[test]
+    fn nothing_to_book() {
+        let groups = [group(vec![9, 10])];
+        assert_eq!(vec![None], book_rooms(&groups, &[], 1));
+        assert_eq!(
+            vec![None],
+            book_rooms(&groups, &[room("Closed", vec![], 3)], 1)
+        );
+        assert_eq!(
+            vec![None],
+            book_rooms(&groups, &[room("Full", vec![9, 10], 0)], 1)
+        );
+        assert_eq!(
+            vec![None],
+            book_rooms(&groups, &[room("Lab", vec![9, 10], 1)], 0)
+        );
+    }
+}
@@ -0,0 +1,349 @@
+/// Builds a sequence of groupings (for example weekly lab pairs) where students meet as many different partners as
+/// possible. When a design that never repeats a pair is known for the class and group size (a round-robin for pairs,
+/// or an affine plane when there are p * p students in groups of a prime p), its rounds come first, with students
+/// placed in it to suit their availability. Other rounds are scheduled like a normal grouping, with every earlier
+/// round counted as partner history, so availability is still taken into account round by round.
+use itertools::Itertools;
+use rand::rng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::student::Student;
+
+use super::hillclimbing_strategy::{Assignment, Problem};
+use super::{
+    DefaultStrategy, DisplayGroup, Group, GroupSizing, Layout, RepeatPartnerRules,
+    SchedulingOptions, SchedulingStrategy, create_groups_with_history, display_groups,
+};
+
+/// Most passes over every pair of students when placing students in a design. Each pass that finds no better placement
+/// ends the search early.
+const MAX_PLACEMENT_PASSES: usize = 10;
+
+/// Rounds of groups in which no two students are grouped together twice, each a list of groups of students numbered
+/// from 0.
+type Design = Vec<Vec<Vec<usize>>>;
+
+/// Groups for every round of a rotation, from `create_rotation`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Rotation {
+    /// The groups for each round, in order. Each group's `suggested_meet_times` are for that round.
+    pub rounds: Vec<Vec<Group>>,
+    /// Number of times a pair of students was grouped in a round after already being grouped in an earlier one.
+    pub repeat_pairings: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DisplayRotation {
+    rounds: Vec<Vec<DisplayGroup>>,
+    repeat_pairings: usize,
+}
+
+#[wasm_bindgen]
+/// Same as `create_rotation`, but suitable for calling from WASM because it takes and returns JSValues.
+/// `students` is a Javascript array of encoded Student (strings).
+/// `sizing` is a `GroupSizing` object, as in `create_groups_wasm`.
+/// `output_timezone` is the timezone which will be used when generating the `suggested_meet_times` array in
+/// each output group.
+/// Returns a JSON object with `rounds` (an array of groups per round, as from `create_groups_wasm`) and
+/// `repeat_pairings`.
+pub fn create_rotation_wasm(
+    students: JsValue,
+    sizing: JsValue,
+    num_rounds: usize,
+    output_timezone: String,
+) -> JsValue {
+    let student_strings: Vec<String> = serde_wasm_bindgen::from_value(students).unwrap();
+    let sizing: GroupSizing = serde_wasm_bindgen::from_value(sizing).unwrap();
+
+    let rotation = create_rotation::<DefaultStrategy>(&student_strings, sizing, num_rounds);
+    let display = DisplayRotation {
+        rounds: rotation
+            .rounds
+            .iter()
+            .map(|groups| display_groups(groups, &output_timezone))
+            .collect(),
+        repeat_pairings: rotation.repeat_pairings,
+    };
+    serde_wasm_bindgen::to_value(&display).unwrap()
+}
+
+/// Creates `num_rounds` groupings of the same students. When a design is known for the sizes (see the module
+/// documentation), no pair repeats for as many rounds as that allows. Otherwise, while every student can still be given
+/// only new partners (as in a round-robin), repeat pairs are forbidden, though the search may not find a grouping that
+/// avoids them all; after that they are only penalised, so repeats are spread out as evenly as the schedules allow.
+pub fn create_rotation<S: SchedulingStrategy>(
+    students_encoded: &[String],
+    sizing: impl Into<GroupSizing>,
+    num_rounds: usize,
+) -> Rotation {
+    let sizing = sizing.into();
+    let students: Vec<Student> = students_encoded
+        .iter()
+        .filter_map(|s| Student::from_encoded(s))
+        .collect();
+    let num_students = students.len();
+    let sizes = sizing.group_sizes(num_students).unwrap_or_default();
+    let largest_group = sizes.iter().copied().max().unwrap_or(1);
+
+    let mut rounds: Vec<Vec<Group>> = designed_rounds(students, &sizes, num_rounds);
+    let mut repeat_pairings = 0;
+    for round in rounds.len()..num_rounds {
+        // Each round gives a student up to `largest_group - 1` new partners, out of `num_students - 1` possible.
+        let all_partners_new = (round + 1) * (largest_group - 1) < num_students;
+        let rules = RepeatPartnerRules {
+            max_times_together: all_partners_new.then_some(1),
+            ..RepeatPartnerRules::default()
+        };
+
+        let previous: Vec<Vec<Vec<String>>> = rounds
+            .iter()
+            .map(|groups| groups.iter().map(|g| g.students.clone()).collect())
+            .collect();
+        let result = create_groups_with_history::<S>(students_encoded, sizing, &previous, rules);
+
+        repeat_pairings += result.repeat_pairings;
+        rounds.push(result.groups);
+    }
+
+    Rotation {
+        rounds,
+        repeat_pairings,
+    }
+}
+
+/// As many of `num_rounds` rounds as a known design gives for `students` in groups of `sizes`, or none if no design is
+/// known for the sizes.
+fn designed_rounds(students: Vec<Student>, sizes: &[usize], num_rounds: usize) -> Vec<Vec<Group>> {
+    let Some(mut design) = sizes
+        .first()
+        .filter(|&&size| sizes.iter().all(|&s| s == size))
+        .and_then(|&size| resolvable_design(students.len(), size))
+    else {
+        return vec![];
+    };
+    design.truncate(num_rounds);
+    let Some(layout) = Layout::new(sizes.to_vec(), &[]) else {
+        return vec![];
+    };
+
+    let problem = Problem::new(students, layout, SchedulingOptions::default());
+    let placement = place_students(&problem, &design);
+    design
+        .iter()
+        .map(|round| {
+            let mut assignment = Assignment::new(&problem.layout);
+            assignment.start_from(
+                &problem,
+                round.iter().flatten().map(|&p| placement[p]).collect(),
+            );
+            assignment.groups(&problem)
+        })
+        .collect()
+}
+
+/// Rounds in which no two of `num_students` students share a group of `group_size` twice, if a construction is known:
+/// a round-robin (the circle method) for pairs, or the parallel classes of an affine plane for p * p students in
+/// groups of a prime p.
+fn resolvable_design(num_students: usize, group_size: usize) -> Option<Design> {
+    if group_size == 2 && num_students >= 2 && num_students.is_multiple_of(2) {
+        // One student stays put while the rest rotate around a circle, each paired with the student opposite.
+        let circle = num_students - 1;
+        return Some(
+            (0..circle)
+                .map(|round| {
+                    (1..num_students / 2)
+                        .map(|i| vec![(round + i) % circle, (round + circle - i) % circle])
+                        .chain([vec![round, circle]])
+                        .collect()
+                })
+                .collect(),
+        );
+    }
+
+    let p = group_size;
+    if p >= 2 && num_students == p * p && (2..p).all(|d| !p.is_multiple_of(d)) {
+        // Students are the points (x, y) of a p by p grid. Each round groups the points on parallel lines: those of
+        // each slope, then the vertical ones.
+        let point = |x: usize, y: usize| x * p + y;
+        let sloped = (0..p).map(|slope| {
+            (0..p)
+                .map(|intercept| {
+                    (0..p)
+                        .map(|x| point(x, (slope * x + intercept) % p))
+                        .collect()
+                })
+                .collect()
+        });
+        let vertical = (0..p)
+            .map(|x| (0..p).map(|y| point(x, y)).collect())
+            .collect();
+        return Some(sloped.chain([vertical]).collect());
+    }
+
+    None
+}
+
+/// Which student to place at each point of `design` so its groups score well for `problem`. Any placement keeps every
+/// pair of students to one group, so this climbs by swapping the points of two students while that scores better.
+fn place_students(problem: &Problem, design: &Design) -> Vec<usize> {
+    let num_students = problem.availability.len();
+    let mut placement = (0..num_students).collect_vec();
+    placement.shuffle(&mut rng());
+
+    let group_of_point: Vec<Vec<usize>> = design
+        .iter()
+        .map(|round| {
+            let mut group_of = vec![0; num_students];
+            for (g, group) in round.iter().enumerate() {
+                for &p in group {
+                    group_of[p] = g;
+                }
+            }
+            group_of
+        })
+        .collect();
+    let score = |placement: &[usize], group: &[usize]| {
+        problem
+            .score_group(&group.iter().map(|&p| placement[p]).collect_vec())
+            .0
+    };
+    let mut scores: Vec<Vec<isize>> = design
+        .iter()
+        .map(|round| round.iter().map(|group| score(&placement, group)).collect())
+        .collect();
+
+    for _ in 0..MAX_PLACEMENT_PASSES {
+        let mut improved = false;
+        for a in 0..num_students {
+            for b in (a + 1)..num_students {
+                placement.swap(a, b);
+                let rescored = (0..design.len())
+                    .filter(|&r| group_of_point[r][a] != group_of_point[r][b])
+                    .flat_map(|r| [(r, group_of_point[r][a]), (r, group_of_point[r][b])])
+                    .map(|(r, g)| (r, g, score(&placement, &design[r][g])))
+                    .collect_vec();
+                let delta: isize = rescored.iter().map(|&(r, g, s)| s - scores[r][g]).sum();
+                if delta > 0 {
+                    for (r, g, s) in rescored {
+                        scores[r][g] = s;
+                    }
+                    improved = true;
+                } else {
+                    placement.swap(a, b);
+                }
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+    placement
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::random::random_students;
+
+    #[test]
+    fn pairs_rotate_through_everyone() {
+        let (students, _) = random_students(4, Some(4));
+        let codes: Vec<String> = students.iter().map(|s| s.encode()).collect();
+
+        let rotation = create_rotation::<DefaultStrategy>(&codes, 2, 3);
+        assert_eq!(3, rotation.rounds.len());
+        assert_eq!(0, rotation.repeat_pairings);
+
+        let pairs: HashSet<Vec<String>> = rotation
+            .rounds
+            .iter()
+            .flatten()
+            .map(|g| g.students.clone())
+            .collect();
+        assert_eq!(6, pairs.len());
+    }
+
+    #[test]
+    fn distinct_partners_in_larger_class() {
+        let (students, _) = random_students(12, Some(12));
+        let codes: Vec<String> = students.iter().map(|s| s.encode()).collect();
+
+        let rotation = create_rotation::<DefaultStrategy>(&codes, 3, 3);
+        assert_eq!(0, rotation.repeat_pairings);
+        for round in &rotation.rounds {
+            assert_eq!(4, round.len());
+            assert!(round.iter().all(|g| !g.suggested_meet_times.is_empty()));
+        }
+    }
+
+    /// Asserts no pair of students is grouped together in more than one round.
+    fn assert_no_repeated_pairs(rounds: &[Vec<Group>]) {
+        let mut pairs = HashSet::new();
+        for group in rounds.iter().flatten() {
+            for pair in group.students.iter().combinations(2) {
+                assert!(pairs.insert(pair), "a pair was grouped twice");
+            }
+        }
+    }
+
+    #[test]
+    fn designs_never_repeat_pairs() {
+        for (num_students, group_size) in [(2, 2), (6, 2), (10, 2), (4, 2), (9, 3), (25, 5)] {
+            let design = resolvable_design(num_students, group_size).unwrap();
+            let rounds: Vec<Vec<Group>> = design
+                .iter()
+                .map(|round| {
+                    round
+                        .iter()
+                        .map(|group| {
+                            assert_eq!(group_size, group.len());
+                            Group {
+                                students: group.iter().map(|p| p.to_string()).collect(),
+                                suggested_meet_times: vec![],
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
+            // Every student meets every other student exactly once.
+            assert_eq!((num_students - 1) / (group_size - 1), rounds.len());
+            assert!(rounds.iter().all(|r| r.len() * group_size == num_students));
+            assert_no_repeated_pairs(&rounds);
+        }
+        assert_eq!(None, resolvable_design(5, 2));
+        assert_eq!(None, resolvable_design(16, 4));
+        assert_eq!(None, resolvable_design(12, 3));
+    }
+
+    #[test]
+    fn no_repeats_over_feasible_rounds() {
+        for (num_students, group_size) in [(8, 2), (9, 3)] {
+            let (students, _) = random_students(num_students, Some(num_students as u64));
+            let codes: Vec<String> = students.iter().map(|s| s.encode()).collect();
+
+            let num_rounds = (num_students - 1) / (group_size - 1);
+            let rotation = create_rotation::<DefaultStrategy>(&codes, group_size, num_rounds);
+            assert_eq!(num_rounds, rotation.rounds.len());
+            assert_eq!(0, rotation.repeat_pairings);
+            assert_no_repeated_pairs(&rotation.rounds);
+            assert!(rotation.rounds.iter().all(|round| {
+                round.iter().map(|g| g.students.len()).sum::<usize>() == num_students
+            }));
+        }
+    }
+
+    #[test]
+    fn repeats_once_partners_run_out() {
+        let (students, _) = random_students(4, Some(5));
+        let codes: Vec<String> = students.iter().map(|s| s.encode()).collect();
+
+        // Only 3 rounds of new partners exist for 4 students in pairs.
+        let rotation = create_rotation::<DefaultStrategy>(&codes, 2, 4);
+        assert_eq!(4, rotation.rounds.len());
+        assert_eq!(2, rotation.repeat_pairings);
+    }
+}
@@ -0,0 +1,277 @@
+/// Controls for how long a strategy searches: a time budget, a handle to cancel the search from elsewhere, and a
+/// callback for progress updates. Strategies stop early when the budget runs out or the search is cancelled, and
+/// return the best assignment found so far.
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// How far a search has got, passed to the progress callback.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    /// Score of the best assignment found so far. Each strategy scores differently, so this is only comparable
+    /// between progress reports of the same search.
+    pub best_score: f64,
+    /// A score no assignment can beat, if the strategy knows one. The strategy stops once it finds an assignment
+    /// scoring this much.
+    pub upper_bound: Option<f64>,
+    /// Number of restarts (starting assignments) the strategy has searched from so far.
+    pub restarts_completed: usize,
+    /// Total number of restarts the strategy will search from if it isn't stopped early.
+    pub total_restarts: usize,
+    /// Time since the search started.
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// How far the best score is below the upper bound, as a fraction of the bound: 0 when the best assignment is
+    /// known to be optimal. None if the strategy doesn't know a bound.
+    pub fn gap(&self) -> Option<f64> {
+        self.upper_bound
+            .map(|bound| optimality_gap(self.best_score, bound))
+    }
+}
+
+/// How far `score` is below `upper_bound`, as a fraction of the bound. Zero if the bound is zero.
+pub(crate) fn optimality_gap(score: f64, upper_bound: f64) -> f64 {
+    if upper_bound > 0.0 {
+        ((upper_bound - score) / upper_bound).max(0.0)
+    } else {
+        0.0
+    }
+}
+
+/// `Progress` as passed to Javascript.
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct DisplayProgress {
+    best_score: f64,
+    upper_bound: Option<f64>,
+    gap: Option<f64>,
+    restarts_completed: usize,
+    total_restarts: usize,
+    elapsed_ms: f64,
+}
+
+impl From<&Progress> for DisplayProgress {
+    fn from(progress: &Progress) -> Self {
+        DisplayProgress {
+            best_score: progress.best_score,
+            upper_bound: progress.upper_bound,
+            gap: progress.gap(),
+            restarts_completed: progress.restarts_completed,
+            total_restarts: progress.total_restarts,
+            elapsed_ms: progress.elapsed.as_secs_f64() * 1000.0,
+        }
+    }
+}
+
+/// Cancels a running search. Clones share the same flag, so one clone can be kept to cancel a search that was
+/// handed the other.
+#[derive(Debug, Clone, Default)]
+pub struct Cancellation(Arc<AtomicBool>);
+
+impl Cancellation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks the search to stop and return the best assignment found so far.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl PartialEq for Cancellation {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+// Searches run on several threads natively and with the `wasm-threads` feature, so the callback must be shareable.
+// Otherwise in the browser everything runs on one thread and callbacks wrap Javascript functions, which can't be
+// shared between threads.
+#[cfg(any(
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "wasm-threads"
+))]
+type ProgressFn = dyn Fn(&Progress) + Send + Sync;
+#[cfg(all(
+    target_arch = "wasm32",
+    target_os = "unknown",
+    not(feature = "wasm-threads")
+))]
+type ProgressFn = dyn Fn(&Progress);
+
+/// Called by a strategy each time it finishes a batch of restarts. Always called from the thread that started
+/// the search.
+#[derive(Clone, Default)]
+pub struct ProgressCallback(Option<Arc<ProgressFn>>);
+
+impl ProgressCallback {
+    #[cfg(any(
+        not(all(target_arch = "wasm32", target_os = "unknown")),
+        feature = "wasm-threads"
+    ))]
+    pub fn new(callback: impl Fn(&Progress) + Send + Sync + 'static) -> Self {
+        ProgressCallback(Some(Arc::new(callback)))
+    }
+
+    #[cfg(all(
+        target_arch = "wasm32",
+        target_os = "unknown",
+        not(feature = "wasm-threads")
+    ))]
+    pub fn new(callback: impl Fn(&Progress) + 'static) -> Self {
+        ProgressCallback(Some(Arc::new(callback)))
+    }
+
+    /// Wraps an optional Javascript function, which is called with a `DisplayProgress`.
+    #[cfg(all(
+        target_arch = "wasm32",
+        target_os = "unknown",
+        not(feature = "wasm-threads")
+    ))]
+    pub(crate) fn from_js(callback: Option<js_sys::Function>) -> Self {
+        let Some(callback) = callback else {
+            return Self::default();
+        };
+        Self::new(move |progress| call_js(&callback, progress))
+    }
+
+    /// Wraps an optional Javascript function, which is called with a `DisplayProgress`. Javascript functions can't be
+    /// shared between threads, so the function is kept by the thread that started the search, which is the thread
+    /// progress is reported on. It's kept until the next search with progress reporting starts on that thread.
+    #[cfg(all(
+        target_arch = "wasm32",
+        target_os = "unknown",
+        feature = "wasm-threads"
+    ))]
+    pub(crate) fn from_js(callback: Option<js_sys::Function>) -> Self {
+        thread_local! {
+            static JS_PROGRESS: std::cell::RefCell<Option<js_sys::Function>> =
+                const { std::cell::RefCell::new(None) };
+        }
+
+        let Some(callback) = callback else {
+            return Self::default();
+        };
+        JS_PROGRESS.with_borrow_mut(|kept| *kept = Some(callback));
+        Self::new(|progress| {
+            JS_PROGRESS.with_borrow(|kept| {
+                if let Some(callback) = kept {
+                    call_js(callback, progress);
+                }
+            })
+        })
+    }
+
+    // Outside WASM there are no Javascript functions to call.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub(crate) fn from_js(_callback: Option<js_sys::Function>) -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn report(&self, progress: &Progress) {
+        if let Some(callback) = &self.0 {
+            callback(progress);
+        }
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+fn call_js(callback: &js_sys::Function, progress: &Progress) {
+    let progress = DisplayProgress::from(progress);
+    let _ = callback.call1(
+        &wasm_bindgen::JsValue::NULL,
+        &serde_wasm_bindgen::to_value(&progress).unwrap(),
+    );
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ProgressCallback")
+            .field(&self.0.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+impl PartialEq for ProgressCallback {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Limits on a search. The default searches until the strategy is done, which can't be cancelled.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchControl {
+    /// Stop searching after this long. None lets the strategy finish.
+    pub time_budget: Option<Duration>,
+    pub cancellation: Cancellation,
+    pub progress: ProgressCallback,
+}
+
+impl SearchControl {
+    /// Starts the clock on the time budget.
+    pub(crate) fn start(&self) -> SearchClock<'_> {
+        SearchClock {
+            control: self,
+            started: OffsetDateTime::now_utc(),
+        }
+    }
+}
+
+/// A started search, used by strategies to check whether they should stop.
+pub(crate) struct SearchClock<'a> {
+    control: &'a SearchControl,
+    // `std::time::Instant` isn't available in the browser, but `time` reads the clock there through Javascript.
+    started: OffsetDateTime,
+}
+
+impl SearchClock<'_> {
+    pub(crate) fn elapsed(&self) -> Duration {
+        elapsed_since(self.started)
+    }
+
+    /// True once the search has been cancelled or has used up its time budget.
+    pub(crate) fn should_stop(&self) -> bool {
+        self.control.cancellation.is_cancelled()
+            || self
+                .control
+                .time_budget
+                .is_some_and(|budget| self.elapsed() >= budget)
+    }
+
+    pub(crate) fn report(
+        &self,
+        best_score: f64,
+        upper_bound: Option<f64>,
+        restarts_completed: usize,
+        total_restarts: usize,
+    ) {
+        self.control.progress.report(&Progress {
+            best_score,
+            upper_bound,
+            restarts_completed,
+            total_restarts,
+            elapsed: self.elapsed(),
+        });
+    }
+}
+
+/// Time from `started` until now, or zero if the clock has gone backwards.
+pub(crate) fn elapsed_since(started: OffsetDateTime) -> Duration {
+    (OffsetDateTime::now_utc() - started)
+        .try_into()
+        .unwrap_or_default()
+}
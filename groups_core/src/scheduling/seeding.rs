@@ -0,0 +1,397 @@
+/// Starting arrangements for the strategies' restarts. Random shuffles spend most of a climb fixing obviously bad
+/// groups, so strategies can instead start from groups built with a cheap greedy pass or by clustering students by
+/// availability (k-medoids on their weekly bitsets, with the number of hours two students don't have in common as
+/// the distance).
+use itertools::Itertools;
+use rand::Rng;
+use rand::seq::{IndexedRandom, SliceRandom};
+
+use serde::{Deserialize, Serialize};
+
+use super::Layout;
+use super::availability::WeekBits;
+
+// Aim for clusters of about this many students. Big enough that most groups fit inside a cluster, small enough that
+// students in a cluster have similar weeks.
+const STUDENTS_PER_CLUSTER: usize = 40;
+
+// k-medoids usually settles within a few rounds. Stop after this many in case it doesn't.
+const MAX_CLUSTERING_ROUNDS: usize = 20;
+
+/// How a strategy arranges students at the start of each restart. Locked students always stay where the layout put
+/// them. Every way is randomized, so restarts start from different arrangements.
+/// In Javascript this is written as `"random"`, `"most_constrained"` or `"similarity"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Seeding {
+    /// Students shuffled at random.
+    Random,
+    /// Students with the fewest available hours go first. Each joins the group whose students so far share the most
+    /// hours with them, where an empty group shares all of their hours. So the most constrained students start groups
+    /// of their own unless a group already shares all their hours.
+    MostConstrained,
+    /// Students clustered by availability and placed cluster by cluster, so students with similar weeks (which often
+    /// means similar timezones) fill the same or neighboring groups.
+    Similarity,
+}
+
+impl Seeding {
+    /// An arrangement of `layout`, given each student's availability in UTC.
+    pub(crate) fn arrangement<R: Rng>(
+        &self,
+        layout: &Layout,
+        availability: &[WeekBits],
+        rng: &mut R,
+    ) -> Vec<usize> {
+        match self {
+            Seeding::Random => layout.shuffled(rng),
+            Seeding::MostConstrained => most_constrained_arrangement(layout, availability, rng),
+            Seeding::Similarity => clustered_arrangement(layout, availability, rng),
+        }
+    }
+}
+
+fn most_constrained_arrangement<R: Rng>(
+    layout: &Layout,
+    availability: &[WeekBits],
+    rng: &mut R,
+) -> Vec<usize> {
+    let mut arrangement = layout.initial().to_vec();
+    let mut is_movable = vec![false; arrangement.len()];
+    for &position in layout.movable() {
+        is_movable[position] = true;
+    }
+
+    // For each group, the hours every student placed in it so far has in common (None before anyone is placed), and
+    // its unlocked positions left.
+    let ranges = layout.group_ranges().collect_vec();
+    let mut common: Vec<Option<WeekBits>> = vec![None; ranges.len()];
+    let mut open = vec![vec![]; ranges.len()];
+    for (g, range) in ranges.into_iter().enumerate() {
+        for position in range {
+            if is_movable[position] {
+                open[g].push(position);
+            } else {
+                let hours = availability[arrangement[position]];
+                common[g] = Some(common[g].map_or(hours, |c| c & hours));
+            }
+        }
+    }
+
+    // Shuffling first breaks ties randomly, both between students and between equally good groups.
+    let mut unlocked = layout
+        .movable()
+        .iter()
+        .map(|&p| arrangement[p])
+        .collect_vec();
+    unlocked.shuffle(rng);
+    unlocked.sort_by_key(|&s| availability[s].count_ones());
+    let mut groups = (0..common.len()).collect_vec();
+    for student in unlocked {
+        let hours = availability[student];
+        // An empty group shares all of the student's hours. On a tie, join a group that already has students.
+        groups.shuffle(rng);
+        let &group = groups
+            .iter()
+            .filter(|&&g| !open[g].is_empty())
+            .max_by_key(|&&g| match common[g] {
+                Some(c) => ((c & hours).count_ones(), true),
+                None => (hours.count_ones(), false),
+            })
+            .unwrap();
+        arrangement[open[group].pop().unwrap()] = student;
+        common[group] = Some(common[group].map_or(hours, |c| c & hours));
+    }
+    arrangement
+}
+
+/// Students with similar availability, and the most central of them.
+struct Cluster {
+    medoid: usize,
+    members: Vec<usize>,
+}
+
+/// An arrangement of `layout` with unlocked students placed cluster by cluster, so students with similar weeks fill
+/// the same or neighboring groups.
+fn clustered_arrangement<R: Rng>(
+    layout: &Layout,
+    availability: &[WeekBits],
+    rng: &mut R,
+) -> Vec<usize> {
+    let mut arrangement = layout.initial().to_vec();
+    let unlocked = layout
+        .movable()
+        .iter()
+        .map(|&p| arrangement[p])
+        .collect_vec();
+    let num_clusters = unlocked.len().div_ceil(STUDENTS_PER_CLUSTER);
+    let clusters = k_medoids(availability, &unlocked, num_clusters, rng);
+
+    // Each cluster is followed by the closest one left, and likewise for students within a cluster, so groups that
+    // straddle two clusters still get students with similar weeks.
+    let medoids = clusters
+        .iter()
+        .map(|c| availability[c.medoid])
+        .collect_vec();
+    let ordered = nearest_neighbor_order(&medoids).into_iter().flat_map(|c| {
+        let members = &clusters[c].members;
+        let weeks = members.iter().map(|&s| availability[s]).collect_vec();
+        nearest_neighbor_order(&weeks)
+            .into_iter()
+            .map(|i| members[i])
+            .collect_vec()
+    });
+
+    for (&position, student) in layout.movable().iter().zip(ordered) {
+        arrangement[position] = student;
+    }
+    arrangement
+}
+
+/// Number of hours of the week only one of two students is available.
+fn distance(a: &WeekBits, b: &WeekBits) -> u32 {
+    (*a ^ *b).count_ones()
+}
+
+/// Splits `students` into at most `num_clusters` clusters of similar availability. Fewer clusters are returned if
+/// there aren't that many different availabilities.
+fn k_medoids<R: Rng>(
+    availability: &[WeekBits],
+    students: &[usize],
+    num_clusters: usize,
+    rng: &mut R,
+) -> Vec<Cluster> {
+    let Some(&first) = students.choose(rng) else {
+        return vec![];
+    };
+
+    // Seed like k-means++: after the first medoid, each is picked with odds proportional to its squared distance from
+    // the closest medoid so far, which spreads them out.
+    let mut medoids = vec![first];
+    let mut closest = students
+        .iter()
+        .map(|&s| distance(&availability[s], &availability[first]))
+        .collect_vec();
+    while medoids.len() < num_clusters {
+        let indices = (0..students.len()).collect_vec();
+        let Ok(&next) = indices.choose_weighted(rng, |&i| closest[i] * closest[i]) else {
+            // Every student has the same availability as a medoid.
+            break;
+        };
+        medoids.push(students[next]);
+        for (i, &s) in students.iter().enumerate() {
+            closest[i] = closest[i].min(distance(&availability[s], &availability[students[next]]));
+        }
+    }
+
+    // Alternate between assigning students to their closest medoid and moving each medoid to the most central member
+    // of its cluster, until the medoids stop moving.
+    let mut clusters = vec![];
+    for _ in 0..MAX_CLUSTERING_ROUNDS {
+        let mut members = vec![vec![]; medoids.len()];
+        for &s in students {
+            let closest_medoid = (0..medoids.len())
+                .min_by_key(|&c| distance(&availability[s], &availability[medoids[c]]))
+                .unwrap();
+            members[closest_medoid].push(s);
+        }
+
+        // A cluster can lose every member if its medoid has the same availability as another.
+        clusters = members
+            .into_iter()
+            .filter(|m| !m.is_empty())
+            .map(|members| Cluster {
+                medoid: most_central(availability, &members),
+                members,
+            })
+            .collect_vec();
+
+        let moved = clusters.iter().map(|c| c.medoid).collect_vec();
+        if moved == medoids {
+            break;
+        }
+        medoids = moved;
+    }
+    clusters
+}
+
+/// The member with the smallest total distance to the others.
+fn most_central(availability: &[WeekBits], members: &[usize]) -> usize {
+    *members
+        .iter()
+        .min_by_key(|&&a| {
+            members
+                .iter()
+                .map(|&b| distance(&availability[a], &availability[b]))
+                .sum::<u32>()
+        })
+        .unwrap()
+}
+
+/// Indices of `weeks` in an order where each is followed by the closest one not yet visited, starting from the first.
+fn nearest_neighbor_order(weeks: &[WeekBits]) -> Vec<usize> {
+    let mut left = (1..weeks.len()).collect_vec();
+    let mut order = Vec::with_capacity(weeks.len());
+    if !weeks.is_empty() {
+        order.push(0);
+    }
+    while !left.is_empty() {
+        let last = weeks[*order.last().unwrap()];
+        let (i, _) = left
+            .iter()
+            .enumerate()
+            .min_by_key(|&(_, &w)| distance(&last, &weeks[w]))
+            .unwrap();
+        order.push(left.swap_remove(i));
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use rand::rngs::StdRng;
+    use rand::{SeedableRng, rng};
+
+    use super::*;
+    use crate::random::random_students;
+    use crate::scheduling::SchedulingOptions;
+    use crate::scheduling::hillclimbing_strategy::{Assignment, Problem};
+
+    #[test]
+    fn clusters_similar_weeks() {
+        // Three kinds of week: mornings, evenings and weekends, each with a few students' extra hours.
+        let kinds = [0..4, 18..22, 120..124];
+        let availability = (0..30)
+            .map(|s| {
+                let kind = kinds[s % 3].clone();
+                let extra = 30 + s;
+                WeekBits::from_hours(kind.chain([extra]))
+            })
+            .collect_vec();
+        let students = (0..30).collect_vec();
+
+        let clusters = k_medoids(&availability, &students, 3, &mut StdRng::seed_from_u64(3));
+        assert_eq!(3, clusters.len());
+        for cluster in &clusters {
+            assert_eq!(10, cluster.members.len());
+            assert!(cluster.members.iter().all(|&s| s % 3 == cluster.medoid % 3));
+        }
+
+        // Only two different weeks, so only two clusters.
+        let availability = (0..10)
+            .map(|s| WeekBits::from_hours(kinds[s % 2].clone()))
+            .collect_vec();
+        let clusters = k_medoids(&availability, &students[..10], 3, &mut rng());
+        assert_eq!(2, clusters.len());
+    }
+
+    #[test]
+    fn most_constrained_groups_students_who_share_hours() {
+        // Students 0 and 1 can only meet Monday mornings and 2 and 3 only Tuesday mornings. 4 to 7 can meet either.
+        let monday = WeekBits::from_hours(0..4);
+        let tuesday = WeekBits::from_hours(24..28);
+        let either = monday ^ tuesday;
+        let availability = [
+            monday, monday, tuesday, tuesday, either, either, either, either,
+        ];
+        let layout = Layout::new(vec![4, 4], &[]).unwrap();
+
+        for _ in 0..10 {
+            let arrangement =
+                Seeding::MostConstrained.arrangement(&layout, &availability, &mut rng());
+            let mut groups = layout
+                .groups(&arrangement)
+                .map(|g| g.iter().copied().sorted().collect_vec())
+                .collect_vec();
+            groups.sort();
+            assert_eq!(2, groups.len());
+            assert_eq!(&groups[0][..2], &[0, 1]);
+            assert_eq!(&groups[1][..2], &[2, 3]);
+        }
+    }
+
+    #[test]
+    fn every_seeding_keeps_locked_students() {
+        let (students, _) = random_students(60, Some(38));
+        let availability = students.iter().map(WeekBits::from_student).collect_vec();
+        let layout = Layout::new(vec![5; 12], &[vec![3, 40], vec![59]]).unwrap();
+
+        for seeding in [
+            Seeding::Random,
+            Seeding::MostConstrained,
+            Seeding::Similarity,
+        ] {
+            let arrangement = seeding.arrangement(&layout, &availability, &mut rng());
+            assert_eq!(
+                (0..60).collect_vec(),
+                arrangement.iter().copied().sorted().collect_vec()
+            );
+            let locked_positions = (0..60).filter(|p| !layout.movable().contains(p));
+            for position in locked_positions {
+                assert_eq!(layout.initial()[position], arrangement[position]);
+            }
+        }
+    }
+
+    // Run with `cargo test --release -- --ignored --nocapture bench_seeding`.
+    #[test]
+    #[ignore]
+    fn bench_seeding() {
+        use crate::scheduling::SchedulingStrategy;
+        use crate::scheduling::hillclimbing_strategy::HillClimbingStrategy;
+
+        let (students, _) = random_students(300, Some(300));
+        let layout = Layout::new(vec![5; 60], &[]).unwrap();
+        for seeding in [
+            Seeding::Random,
+            Seeding::MostConstrained,
+            Seeding::Similarity,
+        ] {
+            let options = SchedulingOptions {
+                seeding: Some(seeding),
+                ..SchedulingOptions::default()
+            };
+            let problem = Problem::new(students.clone(), layout.clone(), options.clone());
+
+            // Score of the starting arrangements alone.
+            const NUM_SEEDS: usize = 20;
+            let started = Instant::now();
+            let seeded_score = (0..NUM_SEEDS)
+                .map(|_| {
+                    let mut assignment = Assignment::new(&layout);
+                    assignment.start_from(
+                        &problem,
+                        seeding.arrangement(&layout, &problem.availability, &mut rng()),
+                    );
+                    assignment.score
+                })
+                .sum::<isize>()
+                / NUM_SEEDS as isize;
+            let seeding_time = started.elapsed() / NUM_SEEDS as u32;
+
+            // Score after a full hill climbing run, rescored the same way.
+            let started = Instant::now();
+            let groups = HillClimbingStrategy::run(&students, &layout, &options);
+            let run_time = started.elapsed();
+            let codes = students.iter().map(|s| s.encode()).collect_vec();
+            let final_score = groups
+                .iter()
+                .map(|g| {
+                    let members = g
+                        .students
+                        .iter()
+                        .map(|code| codes.iter().position(|c| c == code).unwrap())
+                        .collect_vec();
+                    problem.score_group(&members).0
+                })
+                .sum::<isize>();
+
+            println!(
+                "{seeding:?}: starts at {seeded_score} ({seeding_time:?} each), {final_score} after a full run \
+                 ({run_time:?})."
+            );
+        }
+    }
+}
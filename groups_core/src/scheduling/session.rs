@@ -0,0 +1,200 @@
+/// A scheduling search that runs a little at a time, so the instructor page can keep drawing (or run it in a Web
+/// Worker) and show the best groups as they improve instead of freezing until the search is done.
+use time::OffsetDateTime;
+use wasm_bindgen::prelude::*;
+
+use crate::student::Student;
+
+use super::hillclimbing_strategy::IncrementalSearch;
+use super::search::{DisplayProgress, elapsed_since};
+use super::{
+    Group, GroupSizing, LARGE_CLASS_SIZE, Layout, Progress, SchedulingOptions, display_groups,
+    locked_indices,
+};
+
+/// A search for the best grouping of a class, advanced by calling `step`. Searches like `create_groups`: by hill
+/// climbing, or like `ClusteringStrategy` for large classes.
+#[wasm_bindgen]
+pub struct SchedulingSession {
+    search: IncrementalSearch,
+    started: OffsetDateTime,
+}
+
+impl SchedulingSession {
+    /// Starts a search for groups of `students_encoded`, sized by `sizing`, with each of `locked_groups` kept
+    /// together as in `create_groups_with_locked_students`. Returns None if the sizing can't be met or the locked
+    /// groups are invalid.
+    pub fn new(
+        students_encoded: &[String],
+        sizing: impl Into<GroupSizing>,
+        locked_groups: &[Vec<String>],
+    ) -> Option<SchedulingSession> {
+        Self::with_options(
+            students_encoded,
+            sizing,
+            locked_groups,
+            SchedulingOptions::default(),
+        )
+    }
+
+    /// Like `new`, but scores and searches as `options` asks, as `create_groups_with_options` does.
+    pub fn with_options(
+        students_encoded: &[String],
+        sizing: impl Into<GroupSizing>,
+        locked_groups: &[Vec<String>],
+        options: SchedulingOptions,
+    ) -> Option<SchedulingSession> {
+        let (codes, students): (Vec<&String>, Vec<Student>) = students_encoded
+            .iter()
+            .filter_map(|s| Some((s, Student::from_encoded(s)?)))
+            .unzip();
+
+        let locked_indices = locked_indices(&codes, locked_groups)?;
+        let sizes = sizing.into().group_sizes(students.len())?;
+        let layout = Layout::new(sizes, &locked_indices)?;
+
+        let search = if students.len() >= LARGE_CLASS_SIZE {
+            IncrementalSearch::clustering(students, layout, options)
+        } else {
+            IncrementalSearch::new(students, layout, options)
+        };
+        Some(SchedulingSession {
+            search,
+            started: OffsetDateTime::now_utc(),
+        })
+    }
+
+    /// Groups from the best assignment found so far.
+    pub fn best(&self) -> Vec<Group> {
+        self.search.best_groups()
+    }
+
+    pub fn progress(&self) -> Progress {
+        Progress {
+            best_score: self.search.best_score() as f64,
+            upper_bound: Some(self.search.upper_bound() as f64),
+            restarts_completed: self.search.restarts_completed(),
+            total_restarts: self.search.total_restarts(),
+            elapsed: elapsed_since(self.started),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl SchedulingSession {
+    /// Same as `new`, but suitable for calling from WASM because it takes JSValues.
+    /// `students` is a Javascript array of encoded Student (strings).
+    /// `sizing` is a `GroupSizing` object, as in `create_groups_wasm`.
+    /// `locked_groups` is a Javascript array of arrays of encoded Student (may be empty).
+    /// Returns undefined if the sizing can't be met or the locked groups are invalid.
+    #[wasm_bindgen(js_name = create)]
+    pub fn new_wasm(
+        students: JsValue,
+        sizing: JsValue,
+        locked_groups: JsValue,
+    ) -> Option<SchedulingSession> {
+        let student_strings: Vec<String> = serde_wasm_bindgen::from_value(students).unwrap();
+        let sizing: GroupSizing = serde_wasm_bindgen::from_value(sizing).unwrap();
+        let locked: Vec<Vec<String>> = serde_wasm_bindgen::from_value(locked_groups).unwrap();
+
+        Self::new(&student_strings, sizing, &locked)
+    }
+
+    /// Searches `num_neighbors` more candidate groupings. Returns true once the search is done, after which
+    /// stepping does nothing. A few hundred neighbors take a few milliseconds for a typical class.
+    pub fn step(&mut self, num_neighbors: usize) -> bool {
+        self.search.step(num_neighbors)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.search.is_done()
+    }
+
+    /// Same as `best`, but returns a Javascript array of JSON objects representing groups, as from
+    /// `create_groups_wasm`.
+    /// `output_timezone` is the timezone which will be used when generating the `suggested_meet_times` array in
+    /// each output group.
+    #[wasm_bindgen(js_name = best)]
+    pub fn best_wasm(&self, output_timezone: String) -> JsValue {
+        let display = display_groups(&self.best(), &output_timezone);
+        serde_wasm_bindgen::to_value(&display).unwrap()
+    }
+
+    /// Same as `progress`, but returns `{ best_score, upper_bound, gap, restarts_completed, total_restarts, elapsed_ms }`.
+    #[wasm_bindgen(js_name = progress)]
+    pub fn progress_wasm(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&DisplayProgress::from(&self.progress())).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::random_students;
+
+    #[test]
+    fn steps_until_done() {
+        let (students, _) = random_students(12, Some(12));
+        let codes: Vec<String> = students.iter().map(|s| s.encode()).collect();
+        let mut session = SchedulingSession::new(&codes, 3, &[]).unwrap();
+
+        // Best groups are available before any stepping.
+        assert_eq!(4, session.best().len());
+
+        let mut last_score = session.progress().best_score;
+        while !session.step(500) {
+            let progress = session.progress();
+            assert!(progress.best_score >= last_score);
+            last_score = progress.best_score;
+        }
+
+        assert!(session.is_done());
+        let progress = session.progress();
+        // Done after every restart, or sooner if the best groups reached the upper bound.
+        assert!(
+            progress.restarts_completed == progress.total_restarts || progress.gap() == Some(0.0)
+        );
+        assert_eq!(4, session.best().len());
+    }
+
+    #[test]
+    fn keeps_locked_students_together() {
+        let (students, _) = random_students(8, Some(8));
+        let codes: Vec<String> = students.iter().map(|s| s.encode()).collect();
+        let locked = vec![vec![codes[0].clone(), codes[5].clone()]];
+        let mut session = SchedulingSession::new(&codes, 2, &locked).unwrap();
+        session.step(2000);
+
+        let mut pair = locked[0].clone();
+        pair.sort();
+        assert!(session.best().iter().any(|g| g.students == pair));
+    }
+
+    #[test]
+    fn large_classes_search_like_clustering() {
+        let (students, _) = random_students(LARGE_CLASS_SIZE, Some(5));
+        let codes: Vec<String> = students.iter().map(|s| s.encode()).collect();
+        let mut session = SchedulingSession::new(&codes, 5, &[]).unwrap();
+        assert_eq!(
+            super::super::clustering_strategy::NUM_STARTING_POINTS,
+            session.progress().total_restarts
+        );
+
+        while !session.step(10_000) {}
+        assert_eq!(LARGE_CLASS_SIZE / 5, session.best().len());
+    }
+
+    #[test]
+    fn invalid_sessions() {
+        let (students, _) = random_students(4, Some(4));
+        let codes: Vec<String> = students.iter().map(|s| s.encode()).collect();
+
+        assert!(SchedulingSession::new(&codes, GroupSizing::Count(5), &[]).is_none());
+        assert!(SchedulingSession::new(&codes, 2, &[vec!["nope".to_string()]]).is_none());
+
+        let mut empty = SchedulingSession::new(&[], 2, &[]).unwrap();
+        assert!(empty.is_done());
+        assert!(empty.step(10));
+        assert!(empty.best().is_empty());
+    }
+}
@@ -0,0 +1,164 @@
+/// What-if questions about a grouping an instructor is adjusting by hand: how much better or worse the grouping would
+/// score if one student moved to another group, or swapped places with another student.
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use super::hillclimbing_strategy::Problem;
+use super::{Layout, SchedulingOptions};
+use crate::student::Student;
+
+/// One change to a grouping considered by `what_if_moves`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WhatIf {
+    /// Index of the group the student would join.
+    pub to_group: usize,
+    /// Encoded Student the student would swap places with, or None to just move.
+    pub swap_with: Option<String>,
+    /// Change in the grouping's score, scored the way `HillClimbingStrategy` scores groups. Higher is better.
+    pub score_delta: f64,
+    /// Change in the sum over groups of the hours every student in the group is available.
+    pub shared_hours_delta: i64,
+}
+
+#[wasm_bindgen]
+/// Same as `what_if_moves`, but suitable for calling from WASM because it takes and returns JSValues.
+/// `groups` is a Javascript array of arrays of encoded Student, one inner array per group.
+/// `student` is the encoded Student to move.
+/// Returns a Javascript array of `{ to_group, swap_with, score_delta, shared_hours_delta }` objects, best first.
+pub fn what_if_moves_wasm(groups: JsValue, student: String) -> JsValue {
+    let groups: Vec<Vec<String>> = serde_wasm_bindgen::from_value(groups).unwrap();
+    serde_wasm_bindgen::to_value(&what_if_moves(&groups, &student)).unwrap()
+}
+
+/// For `student`, an encoded Student in `groups`, how the grouping would change if they moved to each other group, or
+/// swapped places with each student in another group. Sorted by `score_delta`, then `shared_hours_delta`, best first.
+/// Returns nothing if `student` isn't in `groups`. Codes that don't decode are left out.
+pub fn what_if_moves(groups: &[Vec<String>], student: &str) -> Vec<WhatIf> {
+    let mut codes = vec![];
+    let mut students = vec![];
+    let mut members: Vec<Vec<usize>> = vec![];
+    for group in groups {
+        let mut indices = vec![];
+        for code in group {
+            if let Some(student) = Student::from_encoded(code) {
+                indices.push(students.len());
+                codes.push(code);
+                students.push(student);
+            }
+        }
+        members.push(indices);
+    }
+
+    let Some(moving) = codes.iter().position(|&code| code == student) else {
+        return vec![];
+    };
+    let from_group = members.iter().position(|g| g.contains(&moving)).unwrap();
+
+    // Groups are scored on their own, so the layout only needs to hold every student.
+    let layout = Layout::new(vec![students.len()], &[]).unwrap();
+    let problem = Problem::new(students, layout, SchedulingOptions::default());
+    let value = |group: &[usize]| -> (isize, i64) {
+        if group.is_empty() {
+            (0, 0)
+        } else {
+            (
+                problem.score_group(group).0,
+                problem.shared_hours(group) as i64,
+            )
+        }
+    };
+    let without = |group: &[usize], student: usize| -> Vec<usize> {
+        group.iter().copied().filter(|&s| s != student).collect()
+    };
+    let with = |group: &[usize], student: usize| -> Vec<usize> {
+        group.iter().copied().chain([student]).collect()
+    };
+
+    let from = &members[from_group];
+    let from_before = value(from);
+    let from_after_move = value(&without(from, moving));
+    let mut what_ifs = vec![];
+    for (to_group, to) in members.iter().enumerate() {
+        if to_group == from_group {
+            continue;
+        }
+
+        let to_before = value(to);
+        let mut what_if =
+            |swap_with: Option<usize>, from_after: (isize, i64), to_after: (isize, i64)| {
+                what_ifs.push(WhatIf {
+                    to_group,
+                    swap_with: swap_with.map(|s| codes[s].clone()),
+                    score_delta: (from_after.0 + to_after.0 - from_before.0 - to_before.0) as f64,
+                    shared_hours_delta: from_after.1 + to_after.1 - from_before.1 - to_before.1,
+                })
+            };
+
+        what_if(None, from_after_move, value(&with(to, moving)));
+        for &other in to {
+            what_if(
+                Some(other),
+                value(&with(&without(from, moving), other)),
+                value(&with(&without(to, other), moving)),
+            );
+        }
+    }
+
+    what_ifs.sort_by(|a, b| {
+        b.score_delta
+            .total_cmp(&a.score_delta)
+            .then(b.shared_hours_delta.cmp(&a.shared_hours_delta))
+    });
+    what_ifs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::student_with_hours;
+
+    #[test]
+    fn ranks_moves_and_swaps() {
+        let mornings = [0, 1, 2, 3];
+        let evenings = [20, 21, 22, 23];
+        let (a, b, c) = (
+            student_with_hours("a", "UTC", &mornings).encode(),
+            student_with_hours("b", "UTC", &mornings).encode(),
+            student_with_hours("c", "UTC", &evenings).encode(),
+        );
+        let (d, e) = (
+            student_with_hours("d", "UTC", &evenings).encode(),
+            student_with_hours("e", "UTC", &mornings).encode(),
+        );
+        let groups = vec![
+            vec![a.clone(), b.clone(), c.clone()],
+            vec![d.clone(), e.clone()],
+        ];
+
+        let what_ifs = what_if_moves(&groups, &c);
+        assert_eq!(3, what_ifs.len());
+        assert!(what_ifs.iter().all(|w| w.to_group == 1));
+
+        // Swapping with e makes both groups share four hours: from 2 + 1 to 12 + 8.
+        assert_eq!(Some(e.clone()), what_ifs[0].swap_with);
+        assert_eq!(17.0, what_ifs[0].score_delta);
+        assert_eq!(8, what_ifs[0].shared_hours_delta);
+
+        // Moving leaves a and b sharing four hours, and two of c, d and e at most meeting: 8 + 2.
+        assert_eq!(None, what_ifs[1].swap_with);
+        assert_eq!(7.0, what_ifs[1].score_delta);
+        assert_eq!(4, what_ifs[1].shared_hours_delta);
+
+        // Swapping with d changes nothing.
+        assert_eq!(Some(d.clone()), what_ifs[2].swap_with);
+        assert_eq!(0.0, what_ifs[2].score_delta);
+        assert_eq!(0, what_ifs[2].shared_hours_delta);
+    }
+
+    #[test]
+    fn unknown_student() {
+        let groups = vec![vec![student_with_hours("a", "UTC", &[0]).encode()]];
+        assert!(what_if_moves(&groups, &student_with_hours("b", "UTC", &[0]).encode()).is_empty());
+        assert!(what_if_moves(&groups, "not a student").is_empty());
+    }
+}
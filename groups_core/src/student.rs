@@ -152,6 +152,12 @@ impl Student {
     pub fn timezone(&self) -> String {
         self.timezone.name().to_string()
     }
+
+    /// The student's current offset from UTC, in whole hours.
+    pub(crate) fn utc_offset_hours(&self) -> i8 {
+        let now = OffsetDateTime::now_utc();
+        self.timezone.get_offset_utc(&now).to_utc().whole_hours()
+    }
 }
 
 struct AvailabilityIter {
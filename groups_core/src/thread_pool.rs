@@ -0,0 +1,109 @@
+/// A rayon thread pool for the browser, made of Web Workers sharing the WASM memory. Only built with the
+/// `wasm-threads` feature, which also needs the WASM built with atomics (see `build_threads.sh`) and the page served
+/// cross-origin isolated so it can use shared memory.
+///
+/// Starting the pool: the page calls `init_thread_pool_wasm`, which starts the workers. Once they have loaded the
+/// WASM, `build_thread_pool_wasm` builds the global rayon pool, handing each worker a rayon thread through a channel
+/// that `start_thread_pool_worker_wasm` (run by each worker) receives from.
+///
+/// Work that uses the pool must not run on the page's main thread, which the browser doesn't allow to block while
+/// waiting for the workers. Until the pool is started, strategies climb their starting points one at a time.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::sync::{Mutex, OnceLock};
+
+use rayon::ThreadBuilder;
+use wasm_bindgen::prelude::*;
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[wasm_bindgen(module = "/js/worker_helpers.js")]
+extern "C" {
+    #[wasm_bindgen(js_name = startWorkers)]
+    fn start_workers(module: JsValue, memory: JsValue, num_threads: usize) -> js_sys::Promise;
+}
+
+type ThreadChannel = (Mutex<Sender<ThreadBuilder>>, Mutex<Receiver<ThreadBuilder>>);
+
+fn thread_channel() -> &'static ThreadChannel {
+    static CHANNEL: OnceLock<ThreadChannel> = OnceLock::new();
+    CHANNEL.get_or_init(|| {
+        let (sender, receiver) = channel();
+        (Mutex::new(sender), Mutex::new(receiver))
+    })
+}
+
+/// Whether the thread pool has been started, so strategies can use rayon.
+pub(crate) fn is_started() -> bool {
+    STARTED.load(Ordering::Acquire)
+}
+
+#[wasm_bindgen]
+/// Starts a pool of `num_threads` Web Workers (usually `navigator.hardwareConcurrency`) for strategies to climb
+/// starting points on in parallel. Returns a Promise that resolves once the pool is ready. Call it once.
+pub fn init_thread_pool_wasm(num_threads: usize) -> js_sys::Promise {
+    start_workers(wasm_bindgen::module(), wasm_bindgen::memory(), num_threads)
+}
+
+#[wasm_bindgen]
+/// Called by `worker_helpers.js` once every worker is waiting in `start_thread_pool_worker_wasm`.
+pub fn build_thread_pool_wasm(num_threads: usize) -> Result<(), JsValue> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .spawn_handler(hand_to_worker)
+        .build_global()
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+    STARTED.store(true, Ordering::Release);
+    Ok(())
+}
+
+#[wasm_bindgen]
+/// Run by each worker in `worker_helpers.js`: waits for a rayon thread and runs it, which never returns.
+pub fn start_thread_pool_worker_wasm() {
+    run_handed_thread();
+}
+
+/// Hands a rayon thread of the pool being built to whichever worker is waiting in `run_handed_thread`.
+fn hand_to_worker(thread: ThreadBuilder) -> std::io::Result<()> {
+    thread_channel().0.lock().unwrap().send(thread).unwrap();
+    Ok(())
+}
+
+/// Waits for a rayon thread from `hand_to_worker` and runs it until its pool shuts down.
+fn run_handed_thread() {
+    let thread = thread_channel().1.lock().unwrap().recv().unwrap();
+    thread.run();
+}
+
+#[cfg(test)]
+mod tests {
+    use rayon::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn workers_run_handed_threads() {
+        assert!(!is_started());
+
+        // Stand-ins for the Web Workers, waiting for threads before the pool is built as the workers do.
+        let workers: Vec<_> = (0..2)
+            .map(|_| std::thread::spawn(run_handed_thread))
+            .collect();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .spawn_handler(hand_to_worker)
+            .build()
+            .unwrap();
+
+        let sum: u64 = pool.install(|| (1..=1000u64).into_par_iter().sum());
+        assert_eq!(500_500, sum);
+        assert_eq!(2, pool.current_num_threads());
+
+        // The workers' threads finish once the pool is dropped.
+        drop(pool);
+        for worker in workers {
+            worker.join().unwrap();
+        }
+    }
+}
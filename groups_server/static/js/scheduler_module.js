@@ -1,7 +1,9 @@
-import init, { timezones_wasm, Student, groups_core_init_wasm, create_groups_wasm } from "../pkg/groups_core.js";
+import init, { timezones_wasm, Student, groups_core_init_wasm, SchedulingSession } from "../pkg/groups_core.js";
 
 const SPLIT_REGEX = /[\s,"]+/
 const DEFAULT_TIMEZONE = 'America/Los_Angeles';
+// Candidate groupings to search per animation frame. Small enough to keep the page responsive.
+const NEIGHBORS_PER_FRAME = 300;
 
 init()
     .then(() => {
@@ -33,6 +35,25 @@ function inputTimezoneField() {
     return document.getElementById("inputTimezone")
 }
 
+// The GroupSizing object expected by create_groups_wasm, e.g. { size: 5 } or { count: 4 }.
+function groupSizing() {
+    let mode = document.getElementById("inputGroupSizing").value
+    let value = parseInt(document.getElementById("inputGroupSize").value)
+    return { [mode]: value }
+}
+
+function lockedGroupsField() {
+    return document.getElementById("locked-groups")
+}
+
+// Each non-empty line of the locked groups field is one group of schedule codes.
+function lockedGroups() {
+    return lockedGroupsField().value
+        .split("\n")
+        .map(line => line.split(SPLIT_REGEX).filter(code => code.trim() != ""))
+        .filter(group => group.length > 0)
+}
+
 function populateTimezoneDropdown() {
     let timezones = timezones_wasm()
     timezones.forEach(element => {
@@ -106,10 +127,58 @@ function updateTableDataWithIds() {
 
 function generateGroups() {
     let output_timezone = inputTimezoneField().value;
-    let group_size = document.getElementById("inputGroupSize")
     let spinner = document.getElementById("group-spinner")
+    let progress_text = document.getElementById("group-progress")
+    let submit_button = document.getElementById("submit-button")
     let schedule_ids = scheduleIdsField()
 
+    let schedules = schedule_ids.value.split(SPLIT_REGEX)
+    let session = SchedulingSession.create(schedules, groupSizing(), lockedGroups())
+    if (!session) {
+        alert("These groups can't be made. Check the group size or number of groups, and that every locked student is in the schedule codes, is only locked once, and that each locked group fits in a group.")
+        return
+    }
+
+    spinner.hidden = false
+    progress_text.hidden = false
+    submit_button.disabled = true
+
+    // Search a little each frame, showing the best groups so far, so the page stays responsive.
+    function searchFrame() {
+        let done = session.step(NEIGHBORS_PER_FRAME)
+        let progress = session.progress()
+        progress_text.textContent = "Searched " + progress.restarts_completed + " of " + progress.total_restarts + " starting points"
+        if (progress.gap !== null) {
+            progress_text.textContent += ", within " + Math.round(progress.gap * 100) + "% of the best possible score"
+        }
+        let groups = session.best(output_timezone)
+        showGroups(groups, output_timezone)
+
+        if (!done) {
+            requestAnimationFrame(searchFrame)
+            return
+        }
+
+        session.free()
+
+        // Update schedule ids (and by extension the table)
+        let new_schedule_ids = []
+        for (var group of groups) {
+            for (var student of group.students) {
+                new_schedule_ids.push(student)
+            }
+        }
+        schedule_ids.value = new_schedule_ids.join("\n")
+        updateTableDataWithIds()
+
+        spinner.hidden = true
+        progress_text.hidden = true
+        submit_button.disabled = false
+    }
+    requestAnimationFrame(searchFrame)
+}
+
+function showGroups(groups, output_timezone) {
     // Remove any existing data rows.
     var tableHeaderRowCount = 1;
     var table = document.getElementById("groups-table");
@@ -118,50 +187,29 @@ function generateGroups() {
         table.deleteRow(tableHeaderRowCount);
     }
 
-    spinner.hidden = false
+    // Add new data rows.
+    let table_body = document.getElementById('groups-table-body')
+    var i = 1;
+    for (var group of groups) {
+        var row = table_body.insertRow()
+        var cell = row.insertCell()
+        cell.outerHTML = "<td><div>" + i + "</div></td>"
+
+        cell = row.insertCell()
+        let student_html = "<td><div>"
+        for (var student of group.students) {
+            student_html += Student.from_encoded(student).name() + "<br>"
+        }
+        cell.outerHTML = student_html + "</div></td>"
 
-    // Yield so that the button can update before the heavy computation.
-    requestAnimationFrame(() =>
-        requestAnimationFrame(function () {
-            // Blocks render
-            let schedules = schedule_ids.value.split(SPLIT_REGEX)
-            let groups = create_groups_wasm(schedules, group_size.value, output_timezone)
-
-            // Update schedule ids (and by extension the table)
-            let new_schedule_ids = []
-            for (var group of groups) {
-                for (var student of group.students) {
-                    new_schedule_ids.push(student)
-                }
-            }
-            schedule_ids.value = new_schedule_ids.join("\n")
-            updateTableDataWithIds()
-
-            // Add new data rows.
-            let table_body = document.getElementById('groups-table-body')
-            var i = 1;
-            for (var group of groups) {
-                var row = table_body.insertRow()
-                var cell = row.insertCell()
-                cell.outerHTML = "<td><div>" + i + "</div></td>"
-
-                cell = row.insertCell()
-                let student_html = "<td><div>"
-                for (var student of group.students) {
-                    student_html += Student.from_encoded(student).name() + "<br>"
-                }
-                cell.outerHTML = student_html + "</div></td>"
-
-                cell = row.insertCell()
-                let suggested_meet_times = ""
-                suggested_meet_times += "<b>" + Math.round(group.percent_at_suggested_times * group.students.length) + "/" + group.students.length + " students can meet at these times</b><br>"
-                for (var string of group.suggested_meet_times) {
-                    suggested_meet_times += string + " (" + output_timezone + ")<br>"
-                };
-
-                cell.outerHTML = "<td><div>" + suggested_meet_times + "</div></td>"
-                i++;
-            }
-            spinner.hidden = true
-        }))
-}
\ No newline at end of file
+        cell = row.insertCell()
+        let suggested_meet_times = ""
+        suggested_meet_times += "<b>" + Math.round(group.percent_at_suggested_times * group.students.length) + "/" + group.students.length + " students can meet at these times</b><br>"
+        for (var string of group.suggested_meet_times) {
+            suggested_meet_times += string + " (" + output_timezone + ")<br>"
+        };
+
+        cell.outerHTML = "<td><div>" + suggested_meet_times + "</div></td>"
+        i++;
+    }
+}